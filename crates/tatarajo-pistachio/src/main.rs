@@ -5,7 +5,8 @@ extern crate maplit;
 use big_s::S;
 use itertools::Itertools;
 use tatarajo::action::{self, Action, ActionFnI};
-use tatarajo::input::{KeySeqSerde, Keymap, ModMask};
+use tatarajo::config::Config;
+use tatarajo::input::{KeySeqSerde, Keymap, ModMask, SwipeBinding};
 use tatarajo::view::stackset::WorkspaceTag;
 use tatarajo::TatarajoState;
 
@@ -146,7 +147,13 @@ fn main() -> eyre::Result<()> {
     }));
     let keymap = Keymap::new(keymap);
 
-    TatarajoState::run(workspace_tags, keymap)?;
+    let config = Config {
+        keyseq_cancel: Some(kbd("C-g")),
+        swipe_bindings: SwipeBinding::defaults(),
+        ..Default::default()
+    };
+
+    TatarajoState::run(workspace_tags, keymap, config)?;
 
     Ok(())
 }
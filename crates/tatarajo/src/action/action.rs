@@ -1,5 +1,8 @@
+use crate::process::SpawnOptions;
 use crate::state::TatarajoState;
+use crate::view::stackset::WorkspaceError;
 use dyn_clone::DynClone;
+use std::panic::AssertUnwindSafe;
 
 pub trait ActionFnI: std::fmt::Debug + DynClone {
     fn into_action(self) -> Action
@@ -8,7 +11,18 @@ pub trait ActionFnI: std::fmt::Debug + DynClone {
     {
         Action::ActionFn(self.into())
     }
-    fn exec(&self, state: &mut TatarajoState);
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError>;
+
+    /// Human readable description, e.g. `ActionMoveFocus::Next`. It is shown in the key sequence overlay.
+    fn description(&self) -> String {
+        let name = crate::util::short_type_name::<Self>();
+        let debug = format!("{:?}", self);
+        if debug.starts_with(name) {
+            debug
+        } else {
+            format!("{}::{}", name, debug)
+        }
+    }
 }
 
 dyn_clone::clone_trait_object!(ActionFnI);
@@ -28,37 +42,109 @@ where
 }
 
 impl ActionFn {
-    fn exec(&self, state: &mut TatarajoState) {
-        self.inner.exec(state);
+    /// Executes the action. A panic in it is caught and returned as `ActionError::Panic`.
+    ///
+    /// Actions mainly mutate `StackSet`, which may be left half-updated by a panic. It is restored to the one before
+    /// the action, so that the view stays consistent.
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let stackset = state.inner.view.stackset().clone();
+        match std::panic::catch_unwind(AssertUnwindSafe(|| self.inner.exec(state))) {
+            Ok(result) => result,
+            Err(payload) => {
+                state.inner.view.update_stackset_with(|ss| *ss = stackset);
+                crate::util::panic::clear_root_cause();
+
+                let message = if let Some(s) = payload.downcast_ref::<&str>() {
+                    s.to_string()
+                } else if let Some(s) = payload.downcast_ref::<String>() {
+                    s.clone()
+                } else {
+                    "unknown panic payload".to_string()
+                };
+                Err(ActionError::Panic(message))
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ActionError {
+    #[error("failed to spawn `{command}`: {source}")]
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+    #[error("panicked: {0}")]
+    Panic(String),
+    #[error("{0:#}")]
+    Other(eyre::Report),
+}
+
+impl From<eyre::Report> for ActionError {
+    fn from(x: eyre::Report) -> Self {
+        Self::Other(x)
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum Action {
-    Spawn(String),
+    Spawn(SpawnOptions),
     ActionFn(ActionFn),
 }
 
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Spawn(options) => write!(f, "spawn: {}", options.command),
+            Action::ActionFn(x) => write!(f, "{}", x.inner.description()),
+        }
+    }
+}
+
 impl Action {
     pub fn spawn(s: impl ToString) -> Self {
-        Action::Spawn(s.to_string())
+        Action::Spawn(SpawnOptions::new(s))
     }
 }
 
 impl TatarajoState {
+    /// Executes the action and reports an error if any.
     pub(crate) fn process_action(&mut self, action: &Action) {
+        if let Err(err) = self.try_process_action(action) {
+            error!("action failed: action = {}, error = {}", action, err);
+            if let Some(timeout) = self.inner.config.error_notification_timeout {
+                self.notify(format!("{}: {}", action, err), timeout);
+            }
+        }
+    }
+
+    pub(crate) fn try_process_action(&mut self, action: &Action) -> Result<(), ActionError> {
         info!("{:?}", action);
         match action {
-            Action::Spawn(s) => {
-                let _ = std::process::Command::new("/bin/sh")
-                    .arg("-c")
-                    .arg(s)
-                    .spawn();
+            Action::Spawn(options) => {
+                self.spawn(options).map_err(|source| ActionError::Spawn {
+                    command: options.command.clone(),
+                    source,
+                })?;
+                Ok(())
             }
             Action::ActionFn(f) => {
-                f.exec(self);
+                let focused_window_id = self.inner.view.focused_window().map(|w| w.id());
+
+                // Reflect the view even if the action failed in the middle.
+                let result = f.exec(self);
                 self.inner.view.layout(&mut self.inner.space);
                 self.reflect_focus_from_stackset(None);
+
+                if self.inner.config.mouse_follows_focus
+                    && self.inner.view.focused_window().map(|w| w.id()) != focused_window_id
+                {
+                    self.warp_pointer_to_focused_window();
+                }
+
+                result
             }
         }
     }
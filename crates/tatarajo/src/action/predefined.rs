@@ -1,23 +1,33 @@
-use crate::action::action::{Action, ActionFnI};
+use crate::action::action::{Action, ActionError, ActionFnI};
 use crate::backend::BackendI;
+use crate::process::SpawnOptions;
+use crate::prompt::Prompt;
 use crate::state::TatarajoState;
+use crate::util::glob::glob_match;
+use crate::util::Id;
 use crate::view::stackset::WorkspaceTag;
+use crate::view::view::Direction;
+use crate::view::window::Window;
+use crate::window_picker::WindowPicker;
 
 #[derive(Debug, Clone)]
 pub struct ActionWithSavedFocus(pub Action);
 
 impl ActionFnI for ActionWithSavedFocus {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         // TODO: Save window focus.
 
         let ss = state.inner.view.stackset();
         let ws_index = ss.workspaces.focused_index();
 
-        state.process_action(&self.0);
+        // Restore the workspace before propagating the error.
+        let result = state.try_process_action(&self.0);
 
         state.inner.view.update_stackset_with(|stackset| {
             stackset.workspaces.set_focused_index(ws_index);
         });
+
+        result
     }
 }
 
@@ -25,8 +35,10 @@ impl ActionFnI for ActionWithSavedFocus {
 pub struct ActionQuitTatarajo;
 
 impl ActionFnI for ActionQuitTatarajo {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         state.inner.loop_signal.stop();
+
+        Ok(())
     }
 }
 
@@ -34,8 +46,72 @@ impl ActionFnI for ActionQuitTatarajo {
 pub struct ActionChangeVt(pub i32);
 
 impl ActionFnI for ActionChangeVt {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         state.backend.change_vt(self.0);
+
+        Ok(())
+    }
+}
+
+/// Reads `TATARAJO_OUTPUT_CONFIG` again and applies it to connected outputs.
+#[derive(Debug, Clone)]
+pub struct ActionReloadOutputConfig;
+
+impl ActionFnI for ActionReloadOutputConfig {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state.backend.reload_output_config(&mut state.inner)?;
+
+        Ok(())
+    }
+}
+
+/// Turns all outputs on or off.
+#[derive(Debug, Clone)]
+pub enum ActionDpms {
+    On,
+    Off,
+    /// Turns all outputs off if any is on, and on otherwise.
+    Toggle,
+}
+
+impl ActionFnI for ActionDpms {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let outputs = state.inner.space.outputs().cloned().collect::<Vec<_>>();
+        let on = match self {
+            Self::On => true,
+            Self::Off => false,
+            Self::Toggle => !outputs
+                .iter()
+                .any(|output| state.inner.output_power_state.is_on(output)),
+        };
+        for output in &outputs {
+            state.set_output_power(output, on);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ActionSwitchKeyboardLayout {
+    Next,
+    Prev,
+    /// Index in comma separated layouts of `TATARAJO_XKB_CONFIG`.
+    Index(u32),
+}
+
+impl ActionFnI for ActionSwitchKeyboardLayout {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        use smithay::input::keyboard::Layout;
+
+        let keyboard = state.inner.seat.get_keyboard().unwrap();
+        keyboard.with_xkb_state(state, |mut context| match self {
+            Self::Next => context.cycle_next_layout(),
+            Self::Prev => context.cycle_prev_layout(),
+            Self::Index(i) => context.set_layout(Layout(*i)),
+        });
+
+        Ok(())
     }
 }
 
@@ -46,7 +122,7 @@ pub enum ActionMoveFocus {
 }
 
 impl ActionFnI for ActionMoveFocus {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         let count = match self {
             Self::Next => 1,
             Self::Prev => -1,
@@ -56,6 +132,292 @@ impl ActionFnI for ActionMoveFocus {
             let i = stack.mod_plus_focused_index(count);
             stack.set_focused_index(i);
         });
+
+        Ok(())
+    }
+}
+
+/// Matches windows by their properties. Each field is a glob pattern and all given ones must match.
+#[derive(Debug, Clone, Default)]
+pub struct WindowMatcher {
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+    /// The class of `WM_CLASS` of X11 windows.
+    pub class: Option<String>,
+}
+
+impl WindowMatcher {
+    pub fn matches(&self, window: &Window) -> bool {
+        fn field_matches(pattern: &Option<String>, value: impl FnOnce() -> Option<String>) -> bool {
+            match pattern {
+                None => true,
+                Some(pattern) => value().is_some_and(|value| glob_match(pattern, &value)),
+            }
+        }
+
+        field_matches(&self.app_id, || window.app_id())
+            && field_matches(&self.title, || window.title())
+            && field_matches(&self.class, || window.class())
+    }
+}
+
+/// Focuses a window matching `matcher`, switching workspace if needed, or spawns `command` if no window matches.
+///
+/// If several windows match, repeated executions cycle through them.
+#[derive(Debug, Clone)]
+pub struct ActionRunOrRaise {
+    pub matcher: WindowMatcher,
+    pub command: String,
+}
+
+impl ActionFnI for ActionRunOrRaise {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let view = &state.inner.view;
+        let matched = view
+            .stackset()
+            .workspaces
+            .as_vec()
+            .iter()
+            .flat_map(|ws| ws.stack.as_vec().iter().copied())
+            .filter(|&wid| view.window(wid).is_some_and(|w| self.matcher.matches(w)))
+            .collect::<Vec<_>>();
+
+        if matched.is_empty() {
+            state
+                .spawn(&SpawnOptions::new(&self.command))
+                .map_err(|source| ActionError::Spawn {
+                    command: self.command.clone(),
+                    source,
+                })?;
+            return Ok(());
+        }
+
+        let focus = view.focused_window().map(|w| w.id());
+        let i = match matched.iter().position(|&wid| Some(wid) == focus) {
+            Some(i) => (i + 1) % matched.len(),
+            None => 0,
+        };
+        state.inner.view.set_focus(matched[i]);
+
+        Ok(())
+    }
+}
+
+/// Focuses the window, switching workspace if needed.
+#[derive(Debug, Clone)]
+pub(crate) struct ActionFocusWindow(pub Id<Window>);
+
+impl ActionFnI for ActionFocusWindow {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state.inner.view.set_focus(self.0);
+
+        Ok(())
+    }
+}
+
+/// Lists windows in all workspaces with letter hints. Typing a hint focuses the window, switching workspace if
+/// needed.
+#[derive(Debug, Clone)]
+pub struct ActionWindowPicker;
+
+impl ActionFnI for ActionWindowPicker {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let Some(picker) = WindowPicker::new(&state.inner.view) else {
+            return Ok(());
+        };
+        state.start_key_capture(Box::new(picker));
+
+        Ok(())
+    }
+}
+
+/// Asks a command line and spawns it. `Tab` completes executables in `PATH`.
+#[derive(Debug, Clone)]
+pub struct ActionPromptSpawn;
+
+impl ActionFnI for ActionPromptSpawn {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let prompt = Prompt::new("Run: ", |state, command| {
+            if command.trim().is_empty() {
+                return Ok(());
+            }
+            state
+                .spawn(&SpawnOptions::new(&command))
+                .map_err(|source| ActionError::Spawn { command, source })?;
+            Ok(())
+        })
+        .with_history("spawn")
+        .with_completion(complete_executable);
+        state.start_prompt(prompt);
+
+        Ok(())
+    }
+}
+
+fn complete_executable(text: &str) -> Vec<String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        return vec![];
+    }
+    let Some(path) = std::env::var_os("PATH") else {
+        return vec![];
+    };
+
+    let mut candidates = std::env::split_paths(&path)
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with(text))
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// Asks a part of a window title and focuses the first window whose title contains it, ignoring case. `Tab`
+/// completes titles.
+#[derive(Debug, Clone)]
+pub struct ActionPromptWindowSearch;
+
+impl ActionFnI for ActionPromptWindowSearch {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let view = &state.inner.view;
+        let titles = view
+            .stackset()
+            .workspaces
+            .as_vec()
+            .iter()
+            .flat_map(|ws| ws.stack.as_vec().iter())
+            .filter_map(|&wid| Some((wid, view.window(wid)?.title()?)))
+            .collect::<Vec<_>>();
+        let candidates = titles
+            .iter()
+            .map(|(_, title)| title.clone())
+            .collect::<Vec<_>>();
+
+        let prompt = Prompt::new("Window: ", move |state, text| {
+            let text = text.to_lowercase();
+            let Some(&(window_id, _)) = titles
+                .iter()
+                .find(|(_, title)| title.to_lowercase().contains(&text))
+            else {
+                return Err(eyre::eyre!("no window matches: {}", text).into());
+            };
+            state.inner.view.set_focus(window_id);
+            Ok(())
+        })
+        .with_completion(move |text| {
+            let text = text.to_lowercase();
+            candidates
+                .iter()
+                .filter(|title| title.to_lowercase().contains(&text))
+                .cloned()
+                .collect()
+        });
+        state.start_prompt(prompt);
+
+        Ok(())
+    }
+}
+
+/// Focuses the most recently focused window other than the focused one in the focused workspace.
+#[derive(Debug, Clone)]
+pub struct ActionFocusPrevious;
+
+impl ActionFnI for ActionFocusPrevious {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let ws = state.inner.view.stackset().workspaces.focus();
+        let focus = ws.stack.focus().copied();
+        let Some(&window_id) = ws.history().iter().find(|&&wid| Some(wid) != focus) else {
+            return Ok(());
+        };
+
+        state.inner.view.set_focus(window_id);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActionFocusMaster;
+
+impl ActionFnI for ActionFocusMaster {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state.inner.view.update_stackset_with(|stackset| {
+            let stack = &mut stackset.workspaces.focus_mut().stack;
+            if stack.is_empty() {
+                return;
+            }
+            stack.set_focused_index(0);
+        });
+
+        Ok(())
+    }
+}
+
+/// Swaps the focused window with the master window. Focus moves with the window.
+#[derive(Debug, Clone)]
+pub struct ActionSwapMaster;
+
+impl ActionFnI for ActionSwapMaster {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state.inner.view.update_stackset_with(|stackset| {
+            let stack = &mut stackset.workspaces.focus_mut().stack;
+
+            if stack.is_empty() {
+                return;
+            }
+
+            let mut stack = stack.as_mut();
+            let i = stack.focus;
+            stack.vec.swap(0, i);
+            stack.focus = 0;
+            stack.commit();
+        });
+
+        Ok(())
+    }
+}
+
+/// Rotates all windows in the stack by one. Focus stays on the same window.
+#[derive(Debug, Clone)]
+pub enum ActionRotateStack {
+    /// The master window goes to the tail.
+    Up,
+    /// The tail window goes to the master.
+    Down,
+}
+
+impl ActionFnI for ActionRotateStack {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state.inner.view.update_stackset_with(|stackset| {
+            let stack = &mut stackset.workspaces.focus_mut().stack;
+
+            if stack.is_empty() {
+                return;
+            }
+
+            let count = match self {
+                Self::Up => -1,
+                Self::Down => 1,
+            };
+            let mut stack = stack.as_mut();
+            match self {
+                Self::Up => stack.vec.rotate_left(1),
+                Self::Down => stack.vec.rotate_right(1),
+            }
+            stack.focus = stack.mod_plus_focused_index(count);
+            stack.commit();
+        });
+
+        Ok(())
     }
 }
 
@@ -66,7 +428,7 @@ pub enum ActionWindowSwap {
 }
 
 impl ActionFnI for ActionWindowSwap {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         let count = match self {
             Self::Next => 1,
             Self::Prev => -1,
@@ -85,6 +447,57 @@ impl ActionFnI for ActionWindowSwap {
             stack.focus = j;
             stack.commit();
         });
+
+        Ok(())
+    }
+}
+
+/// Focuses the nearest window in the direction on screen.
+#[derive(Debug, Clone)]
+pub struct ActionFocusDirection(pub Direction);
+
+impl ActionFnI for ActionFocusDirection {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let Some(window_id) = state
+            .inner
+            .view
+            .window_in_direction(self.0, &state.inner.space)
+        else {
+            return Ok(());
+        };
+
+        state.inner.view.set_focus(window_id);
+
+        Ok(())
+    }
+}
+
+/// Swaps the focused window with the nearest window in the direction on screen. Focus moves with the window.
+#[derive(Debug, Clone)]
+pub struct ActionSwapDirection(pub Direction);
+
+impl ActionFnI for ActionSwapDirection {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let Some(window_id) = state
+            .inner
+            .view
+            .window_in_direction(self.0, &state.inner.space)
+        else {
+            return Ok(());
+        };
+
+        state.inner.view.update_stackset_with(|stackset| {
+            let mut stack = stackset.workspaces.focus_mut().stack.as_mut();
+            let Some(j) = stack.vec.iter().position(|&wid| wid == window_id) else {
+                return;
+            };
+            let i = stack.focus;
+            stack.vec.swap(i, j);
+            stack.focus = j;
+            stack.commit();
+        });
+
+        Ok(())
     }
 }
 
@@ -96,20 +509,14 @@ pub enum ActionWorkspaceFocus {
 }
 
 impl ActionFnI for ActionWorkspaceFocus {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         let count = match self {
             Self::Next => 1,
             Self::Prev => -1,
             Self::WithTag(tag) => {
                 let ss = state.inner.view.stackset();
                 let src = ss.workspaces.focused_index();
-                // TODO: Error handling.
-                let dst = ss
-                    .workspaces
-                    .as_vec()
-                    .iter()
-                    .position(|ws| ws.tag == *tag)
-                    .expect("workspace with the given tag exists");
+                let dst = ss.workspace_index(tag)?;
                 dst as isize - src as isize
             }
         };
@@ -118,6 +525,31 @@ impl ActionFnI for ActionWorkspaceFocus {
             let i = workspaces.mod_plus_focused_index(count);
             workspaces.set_focused_index(i);
         });
+
+        Ok(())
+    }
+}
+
+/// Focuses the most recently focused workspace other than the focused one, like xmonad's `toggleWS`.
+#[derive(Debug, Clone)]
+pub struct ActionWorkspaceToggle;
+
+impl ActionFnI for ActionWorkspaceToggle {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let ss = state.inner.view.stackset();
+        let focus = &ss.workspaces.focus().tag;
+        let Some(tag) = ss.workspace_history().iter().find(|&tag| tag != focus) else {
+            return Ok(());
+        };
+        let Some(i) = ss.workspaces.as_vec().iter().position(|ws| ws.tag == *tag) else {
+            return Ok(());
+        };
+
+        state.inner.view.update_stackset_with(|stackset| {
+            stackset.workspaces.set_focused_index(i);
+        });
+
+        Ok(())
     }
 }
 
@@ -128,7 +560,7 @@ pub enum ActionWorkspaceFocusNonEmpty {
 }
 
 impl ActionFnI for ActionWorkspaceFocusNonEmpty {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         let direction = match self {
             Self::Next => 1,
             Self::Prev => -1,
@@ -143,6 +575,8 @@ impl ActionFnI for ActionWorkspaceFocusNonEmpty {
                 }
             }
         });
+
+        Ok(())
     }
 }
 
@@ -154,23 +588,21 @@ pub enum ActionWindowMoveToWorkspace {
 }
 
 impl ActionFnI for ActionWindowMoveToWorkspace {
-    fn exec(&self, state: &mut TatarajoState) {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
         let count = match self {
             Self::Next => 1,
             Self::Prev => -1,
             Self::WithTag(tag) => {
                 let ss = state.inner.view.stackset();
                 let src = ss.workspaces.focused_index();
-                // TODO: Error handling.
-                let dst = ss
-                    .workspaces
-                    .as_vec()
-                    .iter()
-                    .position(|ws| ws.tag == *tag)
-                    .expect("workspace with the given tag exists");
+                let dst = ss.workspace_index(tag)?;
                 dst as isize - src as isize
             }
         };
+        let ss = state.inner.view.stackset();
+        if ss.workspaces.focus().stack.is_empty() {
+            return Ok(());
+        }
         state.inner.view.update_stackset_with(|stackset| {
             let mut workspaces = stackset.workspaces.as_mut();
 
@@ -187,25 +619,125 @@ impl ActionFnI for ActionWindowMoveToWorkspace {
 
             workspaces.commit();
         });
+
+        Ok(())
     }
 }
 
+/// Adds a workspace next to the focused one and focuses it. Without a tag, a number is used.
 #[derive(Debug, Clone)]
-pub struct ActionWindowKill {}
+pub struct ActionWorkspaceAdd(pub Option<WorkspaceTag>);
 
-impl ActionFnI for ActionWindowKill {
-    fn exec(&self, state: &mut TatarajoState) {
-        use smithay::desktop::WindowSurface;
+impl ActionFnI for ActionWorkspaceAdd {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state
+            .inner
+            .view
+            .update_stackset_with(|stackset| stackset.add_workspace(self.0.clone()))?;
 
-        let Some(window) = state.inner.view.focused_window_mut() else {
-            return;
-        };
+        Ok(())
+    }
+}
+
+/// Renames the focused workspace.
+#[derive(Debug, Clone)]
+pub struct ActionWorkspaceRename(pub WorkspaceTag);
+
+impl ActionFnI for ActionWorkspaceRename {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state
+            .inner
+            .view
+            .update_stackset_with(|stackset| stackset.rename_workspace(self.0.clone()))?;
 
-        match window.smithay_window().underlying_surface() {
-            WindowSurface::Wayland(w) => w.send_close(),
-            WindowSurface::X11(w) => {
-                let _ = w.close();
+        Ok(())
+    }
+}
+
+/// Asks a name and adds a workspace with it, like `ActionWorkspaceAdd`. An empty name means a number.
+#[derive(Debug, Clone)]
+pub struct ActionPromptWorkspaceAdd;
+
+impl ActionFnI for ActionPromptWorkspaceAdd {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state.start_prompt(Prompt::new("Workspace name: ", |state, name| {
+            let tag = (!name.is_empty()).then_some(WorkspaceTag(name));
+            ActionWorkspaceAdd(tag).exec(state)
+        }));
+
+        Ok(())
+    }
+}
+
+/// Asks a name and renames the focused workspace with it.
+#[derive(Debug, Clone)]
+pub struct ActionPromptWorkspaceRename;
+
+impl ActionFnI for ActionPromptWorkspaceRename {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        state.start_prompt(Prompt::new("Rename workspace: ", |state, name| {
+            if name.is_empty() {
+                return Ok(());
             }
+            ActionWorkspaceRename(WorkspaceTag(name)).exec(state)
+        }));
+
+        Ok(())
+    }
+}
+
+/// Removes the focused workspace.
+#[derive(Debug, Clone)]
+pub enum ActionWorkspaceRemove {
+    /// Fails if the workspace has windows.
+    IfEmpty,
+    /// Moves windows to the workspace focused next.
+    Merge,
+}
+
+impl ActionFnI for ActionWorkspaceRemove {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let merge = matches!(self, Self::Merge);
+        state
+            .inner
+            .view
+            .update_stackset_with(|stackset| stackset.remove_workspace(merge))?;
+
+        Ok(())
+    }
+}
+
+/// Moves the focused workspace in the order of workspaces.
+#[derive(Debug, Clone)]
+pub enum ActionWorkspaceMove {
+    Next,
+    Prev,
+}
+
+impl ActionFnI for ActionWorkspaceMove {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let count = match self {
+            Self::Next => 1,
+            Self::Prev => -1,
+        };
+        state.inner.view.update_stackset_with(|stackset| {
+            stackset.move_workspace(count);
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActionWindowKill {}
+
+impl ActionFnI for ActionWindowKill {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        let Some(window) = state.inner.view.focused_window() else {
+            return Ok(());
         };
+        window.close();
+
+        Ok(())
     }
 }
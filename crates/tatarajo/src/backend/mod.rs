@@ -21,6 +21,10 @@ pub(crate) trait BackendI: DmabufHandlerDelegate {
     fn early_import(&mut self, surface: &wayland_server::protocol::wl_surface::WlSurface);
     fn update_led_state(&mut self, led_state: smithay::input::keyboard::LedState);
     fn change_vt(&mut self, vt: i32);
+    /// Reads `TATARAJO_OUTPUT_CONFIG` again and applies it to connected outputs.
+    fn reload_output_config(&mut self, inner: &mut crate::state::InnerState) -> eyre::Result<()>;
+    /// Turns the output on or off. Outputs are not rendered while they are off.
+    fn set_output_power(&mut self, output: &smithay::output::Output, on: bool);
 }
 
 #[derive(derive_more::From)]
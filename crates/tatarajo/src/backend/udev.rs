@@ -1,8 +1,12 @@
 use crate::backend::BackendI;
-use crate::envvar::EnvVar;
+use crate::envvar::{
+    EnvVar, LibinputAccelProfile, LibinputDeviceConfig, LibinputDeviceType, OutputConfig,
+    OutputModeConfig,
+};
 use crate::pointer::{PointerElement, CLEAR_COLOR};
 use crate::render::{output_elements, CustomRenderElement};
 use crate::render_loop::RenderLoop;
+use crate::session_lock::SessionLockState;
 use crate::state::{
     post_repaint, take_presentation_feedback, InnerState, SurfaceDmabufFeedback, TatarajoState,
     TatarajoStateWithConcreteBackend,
@@ -58,6 +62,7 @@ use smithay::wayland::dmabuf::{DmabufFeedback, DmabufFeedbackBuilder, DmabufGlob
 use smithay::wayland::drm_lease::{
     DrmLease, DrmLeaseBuilder, DrmLeaseHandler, DrmLeaseRequest, DrmLeaseState, LeaseRejected,
 };
+use smithay::wayland::tablet_manager::{TabletDescriptor, TabletSeatTrait};
 use smithay_drm_extras::drm_scanner::{DrmScanEvent, DrmScanner};
 use smithay_drm_extras::edid::EdidInfo;
 use std::collections::hash_map::HashMap;
@@ -107,6 +112,9 @@ pub(crate) struct UdevBackend {
     // Input
     libinput_context: libinput::Libinput,
     input_devices: HashSet<libinput::Device>,
+    libinput_config: Vec<LibinputDeviceConfig>,
+
+    output_config: Vec<OutputConfig>,
 }
 
 impl UdevBackend {
@@ -158,6 +166,18 @@ impl UdevBackend {
             dev_path_or_na(&selected_render_node)
         );
 
+        let libinput_config = envvar
+            .libinput_config()
+            .wrap_err("parse TATARAJO_LIBINPUT_CONFIG")?;
+        let output_config = envvar
+            .output_config()
+            .wrap_err("load TATARAJO_OUTPUT_CONFIG")?;
+        if std::env::var_os("TATARAJO_DISABLE_10BIT").is_some() {
+            warn!(
+                "TATARAJO_DISABLE_10BIT is no longer supported and ignored. Use `\"prefer_10bit\": false` in TATARAJO_OUTPUT_CONFIG instead."
+            );
+        }
+
         let gpus = GpuManager::new(GbmGlesBackend::with_context_priority(ContextPriority::High))?;
 
         let mut libinput_context =
@@ -190,6 +210,8 @@ impl UdevBackend {
             debug_flags: DebugFlags::empty(),
             libinput_context,
             input_devices: HashSet::new(),
+            libinput_config,
+            output_config,
         })
     }
 }
@@ -269,6 +291,14 @@ impl BackendI for UdevBackend {
 
         // init dmabuf support with format list from selected render node
         let dmabuf_formats = renderer.dmabuf_formats().collect::<Vec<_>>();
+        inner.screencopy_state.set_dmabuf_constraints(
+            self.selected_render_node.dev_id(),
+            dmabuf_formats
+                .iter()
+                .filter(|format| format.code == Fourcc::Xrgb8888)
+                .map(|format| format.modifier)
+                .collect(),
+        );
         let default_feedback =
             DmabufFeedbackBuilder::new(self.selected_render_node.dev_id(), dmabuf_formats)
                 .build()?;
@@ -338,6 +368,78 @@ impl BackendI for UdevBackend {
             warn!("changing VT failed: {e}");
         }
     }
+
+    fn reload_output_config(&mut self, inner: &mut InnerState) -> eyre::Result<()> {
+        self.output_config = inner
+            .envvar
+            .output_config()
+            .wrap_err("load TATARAJO_OUTPUT_CONFIG")?;
+
+        // Set up outputs whose config changed again, as a mode and color formats are fixed on creation of surfaces.
+        // Others are kept as they are, including their power and lock surfaces.
+        let mut connectors = vec![];
+        for (node, device) in &self.backends {
+            for (info, crtc) in device.drm_scanner.crtcs() {
+                let non_desktop = device
+                    .non_desktop_connectors
+                    .iter()
+                    .any(|(handle, _)| *handle == info.handle());
+                if non_desktop {
+                    continue;
+                }
+                let config = resolve_output_config(&self.output_config, &device.drm, info);
+                if device.output_configs.get(&info.handle()) != Some(&config) {
+                    connectors.push((*node, info.clone(), crtc));
+                }
+            }
+        }
+
+        let mut as_udev_mut = TatarajoStateWithConcreteBackend {
+            backend: self,
+            inner,
+        };
+        for (node, connector, crtc) in connectors {
+            let was_off = as_udev_mut
+                .output_for_crtc(node, crtc)
+                .is_some_and(|output| !as_udev_mut.inner.output_power_state.is_on(&output));
+            as_udev_mut.connector_disconnected(node, connector.clone(), crtc);
+            as_udev_mut.connector_connected(node, connector, crtc);
+            if was_off {
+                if let Some(output) = as_udev_mut.output_for_crtc(node, crtc) {
+                    as_udev_mut.backend.set_output_power(&output, false);
+                    as_udev_mut.inner.output_power_state.keep_off(&output);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_output_power(&mut self, output: &smithay::output::Output, on: bool) {
+        let Some(id) = output.user_data().get::<UdevOutputId>() else {
+            return;
+        };
+        let Some(surface) = self
+            .backends
+            .get_mut(&id.primary_node)
+            .and_then(|backend| backend.surfaces.get_mut(&id.crtc))
+        else {
+            return;
+        };
+
+        if on {
+            // The next frame enables the CRTC again.
+            if let Err(err) = surface.compositor.reset_state() {
+                warn!("Failed to reset drm surface state: {}", err);
+            }
+            surface.render_loop.start();
+        } else {
+            surface.render_loop.stop();
+            if let Err(err) = surface.compositor.clear() {
+                warn!("Failed to turn off drm surface: {}", err);
+            }
+        }
+    }
 }
 
 impl DrmLeaseHandler for TatarajoState {
@@ -472,6 +574,17 @@ impl SurfaceComposition {
         }
     }
 
+    /// Disables the CRTC until the next frame is rendered.
+    fn clear(&mut self) -> Result<(), SwapBuffersError> {
+        match self {
+            SurfaceComposition::Compositor(c) => c.clear().map_err(Into::<SwapBuffersError>::into),
+            SurfaceComposition::Surface { surface, .. } => surface
+                .surface()
+                .clear()
+                .map_err(Into::<SwapBuffersError>::into),
+        }
+    }
+
     fn reset_state(&mut self) -> Result<(), SwapBuffersError> {
         match self {
             SurfaceComposition::Compositor(c) => {
@@ -588,6 +701,8 @@ struct SurfaceData {
 
 struct BackendData {
     surfaces: HashMap<crtc::Handle, SurfaceData>,
+    /// Resolved configs of desktop connectors, including disabled ones. Used to find outputs to set up again on reload.
+    output_configs: HashMap<connector::Handle, OutputConfig>,
     non_desktop_connectors: Vec<(connector::Handle, crtc::Handle)>,
     leasing_global: Option<DrmLeaseState>,
     active_leases: Vec<DrmLease>,
@@ -748,6 +863,7 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
                 non_desktop_connectors: Vec::new(),
                 render_node,
                 surfaces: HashMap::new(),
+                output_configs: HashMap::new(),
                 leasing_global: DrmLeaseState::new::<TatarajoState>(
                     &self.inner.display_handle,
                     &node,
@@ -794,11 +910,7 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
                 .dmabuf_render_formats()
                 .clone();
 
-            let output_name = format!(
-                "{}-{}",
-                connector.interface().as_str(),
-                connector.interface_id()
-            );
+            let output_name = connector_name(&connector);
             info!(?crtc, "Trying to setup connector {}", output_name);
 
             let non_desktop = device
@@ -822,6 +934,7 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
             let (make, model) = EdidInfo::for_connector(&device.drm, connector.handle())
                 .map(|info| (info.manufacturer, info.model))
                 .unwrap_or_else(|| ("Unknown".into(), "Unknown".into()));
+            let serial = edid_serial(&device.drm, connector.handle());
 
             if non_desktop {
                 info!(
@@ -839,6 +952,21 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
                     );
                 }
             } else {
+                let config = OutputConfig::resolve(
+                    &self.backend.output_config,
+                    &output_name,
+                    &make,
+                    &model,
+                    serial.as_deref(),
+                );
+                device
+                    .output_configs
+                    .insert(connector.handle(), config.clone());
+                if !config.enabled() {
+                    info!("Connector {} is disabled by config", output_name);
+                    return Ok(());
+                }
+
                 let (phys_w, phys_h) = connector.size().unwrap_or((0, 0));
                 let output = smithay::output::Output::new(
                     output_name,
@@ -854,10 +982,16 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
                     self.inner.display_handle.clone(),
                 );
 
-                let x = self.inner.space.outputs().fold(0, |acc, o| {
-                    acc + self.inner.space.output_geometry(o).unwrap().size.w
-                });
-                let position = (x, 0).into();
+                let position = match config.position {
+                    Some([x, y]) => (x, y).into(),
+                    None => {
+                        let x = self.inner.space.outputs().fold(0, |acc, o| {
+                            let geometry = self.inner.space.output_geometry(o).unwrap();
+                            acc.max(geometry.loc.x + geometry.size.w)
+                        });
+                        (x, 0).into()
+                    }
+                };
 
                 for (i, mode) in connector.modes().iter().enumerate() {
                     let dpi = calc_estimated_dpi(&connector, mode);
@@ -867,26 +1001,34 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
                     );
                 }
 
-                let mode = *connector
-                    .modes()
-                    .iter()
-                    .find(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
-                    .unwrap_or(&connector.modes()[0]);
-                let scale = calc_output_scale(&connector, &mode);
+                let mode = select_mode(&connector, config.mode.as_ref());
+                let scale = config
+                    .scale()
+                    .unwrap_or_else(|| calc_output_scale(&connector, &mode));
+                let transform = config.transform.map_or(Transform::Normal, Into::into);
                 info!(
-                    "selected: mode = {:?}, scale = {:?}, estimated_dpi = {:?}, corrected_dpi = {:?}",
+                    "selected: mode = {:?}, scale = {:?}, transform = {:?}, position = {:?}, estimated_dpi = {:?}, corrected_dpi = {:?}",
                     mode,
                     scale,
+                    transform,
+                    position,
                     calc_estimated_dpi(&connector, &mode),
                     calc_estimated_dpi(&connector, &mode).map(|x| x / scale.fractional_scale())
                 );
                 output.set_preferred(mode.into());
-                output.change_current_state(Some(mode.into()), None, Some(scale), Some(position));
+                output.change_current_state(
+                    Some(mode.into()),
+                    Some(transform),
+                    Some(scale),
+                    Some(position),
+                );
                 self.inner.space.map_output(&output, position);
                 let size = self.inner.space.output_geometry(&output)
                     .unwrap(/* Space::map_output() and Output::change_current_state() is called. */)
                     .size;
                 self.inner.view.resize_output(size, &mut self.inner.space);
+                self.inner.refresh_ext_workspace();
+                self.inner.refresh_foreign_toplevel();
 
                 output.user_data().insert_if_missing(|| UdevOutputId {
                     primary_node: node,
@@ -898,10 +1040,10 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
                     GbmBufferFlags::RENDERING | GbmBufferFlags::SCANOUT,
                 );
 
-                let color_formats = if self.inner.envvar.tatarajo.disable_10bit {
-                    SUPPORTED_FORMATS_8BIT_ONLY
-                } else {
+                let color_formats = if config.prefer_10bit() {
                     SUPPORTED_FORMATS
+                } else {
+                    SUPPORTED_FORMATS_8BIT_ONLY
                 };
 
                 let surface = device
@@ -997,6 +1139,24 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
         }
     }
 
+    /// Returns the output of the CRTC if it is set up.
+    fn output_for_crtc(
+        &self,
+        node: DrmNode,
+        crtc: crtc::Handle,
+    ) -> Option<smithay::output::Output> {
+        self.inner
+            .space
+            .outputs()
+            .find(|o| {
+                o.user_data()
+                    .get::<UdevOutputId>()
+                    .map(|id| id.primary_node == node && id.crtc == crtc)
+                    .unwrap_or(false)
+            })
+            .cloned()
+    }
+
     fn connector_disconnected(
         &mut self,
         node: DrmNode,
@@ -1020,21 +1180,13 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
             }
         } else {
             device.surfaces.remove(&crtc);
+            device.output_configs.remove(&connector.handle());
 
-            let output = self
-                .inner
-                .space
-                .outputs()
-                .find(|o| {
-                    o.user_data()
-                        .get::<UdevOutputId>()
-                        .map(|id| id.primary_node == node && id.crtc == crtc)
-                        .unwrap_or(false)
-                })
-                .cloned();
-
-            if let Some(output) = output {
+            if let Some(output) = self.output_for_crtc(node, crtc) {
                 self.inner.space.unmap_output(&output);
+                self.inner.refresh_ext_workspace();
+                self.inner.refresh_foreign_toplevel();
+                self.inner.output_power_state.remove_output(&output);
             }
         }
     }
@@ -1282,19 +1434,45 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
             // somehow we got called with an invalid output
             return;
         };
+        if !self.inner.output_power_state.is_on(&output) {
+            // Nothing is shown, which is as good as a locked frame.
+            self.inner
+                .session_lock_state
+                .on_locked_frame(&output, &self.inner.space);
+            return;
+        }
 
-        let result = render_surface(
-            surface,
+        let output_geometry = self.inner.space.output_geometry(&output).unwrap();
+        let mut custom_elements = cursor_elements(
             &mut renderer,
-            &self.inner.space,
-            &output,
+            output_geometry,
+            output.current_scale().fractional_scale().into(),
             self.inner.pointer.current_location(),
             &pointer_image,
             &mut self.backend.pointer_element,
             &self.inner.dnd_icon,
             &mut self.inner.cursor_status.lock().unwrap(),
+            self.inner.tablet_tool_cursors.visible_cursors(),
+        );
+        // Overlays are drawn below the pointer.
+        custom_elements.extend(self.inner.overlay_elements(&mut renderer, &output));
+
+        let is_locked = self.inner.session_lock_state.is_locked();
+        let result = render_surface(
+            surface,
+            &mut renderer,
+            custom_elements,
+            &self.inner.space,
+            &self.inner.session_lock_state,
+            &output,
             &self.inner.clock,
         );
+        if is_locked && result.is_ok() {
+            // The frame is queued, or the last one is still locked if nothing is damaged.
+            self.inner
+                .session_lock_state
+                .on_locked_frame(&output, &self.inner.space);
+        }
         let should_reschedule_render = match &result {
             Ok(has_rendered) => !has_rendered,
             Err(err) => {
@@ -1326,6 +1504,24 @@ impl TatarajoStateWithConcreteBackend<'_, UdevBackend> {
             }
         };
 
+        self.inner.render_captures::<_, GlesTexture, _>(
+            &mut renderer,
+            &output,
+            |renderer, inner| {
+                cursor_elements(
+                    renderer,
+                    output_geometry,
+                    output.current_scale().fractional_scale().into(),
+                    inner.pointer.current_location(),
+                    &pointer_image,
+                    &mut self.backend.pointer_element,
+                    &inner.dnd_icon,
+                    &mut inner.cursor_status.lock().unwrap(),
+                    inner.tablet_tool_cursors.visible_cursors(),
+                )
+            },
+        );
+
         // TODO: Check that this is reasonable for the above `Err` case.
         surface
             .render_loop
@@ -1381,7 +1577,96 @@ fn calc_estimated_dpi(connector: &connector::Info, mode: &drm::control::Mode) ->
     })
 }
 
-// TODO: Config
+/// Returns the mode of the given size with the closest refresh rate, or the highest one if the refresh rate is not
+/// given. Falls back to the preferred mode.
+fn select_mode(
+    connector: &connector::Info,
+    config: Option<&OutputModeConfig>,
+) -> drm::control::Mode {
+    let preferred = *connector
+        .modes()
+        .iter()
+        .find(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
+        .unwrap_or(&connector.modes()[0]);
+    let Some(config) = config else {
+        return preferred;
+    };
+
+    // In mHz.
+    let refresh = |mode: &drm::control::Mode| smithay::output::Mode::from(*mode).refresh;
+    let candidates = connector
+        .modes()
+        .iter()
+        .filter(|mode| mode.size() == (config.width, config.height));
+    let mode = match config.refresh {
+        Some(hz) => {
+            let target = (hz * 1000.0).round() as i32;
+            candidates.min_by_key(|mode| (refresh(mode) - target).abs())
+        }
+        None => candidates.max_by_key(|mode| refresh(mode)),
+    };
+    match mode {
+        Some(mode) => *mode,
+        None => {
+            warn!(
+                "mode {:?} is not supported by the connector, falling back to the preferred mode",
+                config
+            );
+            preferred
+        }
+    }
+}
+
+/// Returns the name of the connector, e.g. `DP-1`, which is used as the output name.
+fn connector_name(connector: &connector::Info) -> String {
+    format!(
+        "{}-{}",
+        connector.interface().as_str(),
+        connector.interface_id()
+    )
+}
+
+/// Merges rules of `TATARAJO_OUTPUT_CONFIG` for the connector, in the same way as when it is connected.
+fn resolve_output_config(
+    rules: &[OutputConfig],
+    drm: &DrmDevice,
+    connector: &connector::Info,
+) -> OutputConfig {
+    let (make, model) = EdidInfo::for_connector(drm, connector.handle())
+        .map(|info| (info.manufacturer, info.model))
+        .unwrap_or_else(|| ("Unknown".into(), "Unknown".into()));
+    let serial = edid_serial(drm, connector.handle());
+    OutputConfig::resolve(
+        rules,
+        &connector_name(connector),
+        &make,
+        &model,
+        serial.as_deref(),
+    )
+}
+
+/// Reads the serial number from EDID. The serial number string is preferred to the numeric one.
+fn edid_serial(drm: &DrmDevice, connector: connector::Handle) -> Option<String> {
+    let props = drm.get_properties(connector).ok()?;
+    let blob = props.into_iter().find_map(|(handle, value)| {
+        let info = drm.get_property(handle).ok()?;
+        (info.name().to_str() == Ok("EDID")).then_some(value)
+    })?;
+    let data = drm.get_property_blob(blob).ok()?;
+    let edid = edid_rs::parse(&mut std::io::Cursor::new(data)).ok()?;
+
+    edid.descriptors
+        .0
+        .iter()
+        .find_map(|descriptor| match descriptor {
+            edid_rs::MonitorDescriptor::SerialNumber(serial) => Some(serial.trim().to_string()),
+            _ => None,
+        })
+        .or_else(|| {
+            (edid.product.serial_number != 0).then(|| edid.product.serial_number.to_string())
+        })
+}
+
 fn calc_output_scale(
     connector: &connector::Info,
     mode: &drm::control::Mode,
@@ -1408,38 +1693,38 @@ fn calc_output_scale(
     }
 }
 
+fn cursor_hotspot(cursor_status: &CursorImageStatus) -> Point<i32, Logical> {
+    if let CursorImageStatus::Surface(ref surface) = cursor_status {
+        compositor::with_states(surface, |states| {
+            states
+                .data_map
+                .get::<Mutex<CursorImageAttributes>>()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .hotspot
+        })
+    } else {
+        (0, 0).into()
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
-fn render_surface<'a>(
-    surface: &'a mut SurfaceData,
+fn cursor_elements<'a>(
     renderer: &mut UdevRenderer<'a>,
-    space: &Space<crate::view::window::Window>,
-    output: &smithay::output::Output,
+    output_geometry: Rectangle<i32, Logical>,
+    scale: Scale<f64>,
     pointer_location: Point<f64, Logical>,
     pointer_image: &MemoryRenderBuffer,
     pointer_element: &mut PointerElement,
     dnd_icon: &Option<wayland_server::protocol::wl_surface::WlSurface>,
     cursor_status: &mut CursorImageStatus,
-    clock: &Clock<Monotonic>,
-) -> Result<bool, SwapBuffersError> {
-    let output_geometry = space.output_geometry(output).unwrap();
-    let scale = Scale::from(output.current_scale().fractional_scale());
-
+    tablet_tool_cursors: Vec<(Point<f64, Logical>, CursorImageStatus)>,
+) -> Vec<CustomRenderElement<UdevRenderer<'a>>> {
     let mut custom_elements: Vec<CustomRenderElement<_>> = Vec::new();
 
     if output_geometry.to_f64().contains(pointer_location) {
-        let cursor_hotspot = if let CursorImageStatus::Surface(ref surface) = cursor_status {
-            compositor::with_states(surface, |states| {
-                states
-                    .data_map
-                    .get::<Mutex<CursorImageAttributes>>()
-                    .unwrap()
-                    .lock()
-                    .unwrap()
-                    .hotspot
-            })
-        } else {
-            (0, 0).into()
-        };
+        let cursor_hotspot = cursor_hotspot(cursor_status);
         let cursor_pos = pointer_location - output_geometry.loc.to_f64() - cursor_hotspot.to_f64();
         let cursor_pos_scaled = cursor_pos.to_physical(scale).to_i32_round();
 
@@ -1483,7 +1768,37 @@ fn render_surface<'a>(
         }
     }
 
-    let (elements, clear_color) = output_elements(renderer, output, space, custom_elements);
+    for (location, status) in tablet_tool_cursors {
+        if !output_geometry.to_f64().contains(location) {
+            continue;
+        }
+
+        let cursor_pos = location - output_geometry.loc.to_f64() - cursor_hotspot(&status).to_f64();
+        let mut element = PointerElement::default();
+        element.set_buffer(pointer_image.clone());
+        element.set_status(status);
+        custom_elements.extend(element.render_elements(
+            renderer,
+            cursor_pos.to_physical(scale).to_i32_round(),
+            scale,
+            1.0,
+        ));
+    }
+
+    custom_elements
+}
+
+fn render_surface<'a>(
+    surface: &'a mut SurfaceData,
+    renderer: &mut UdevRenderer<'a>,
+    custom_elements: Vec<CustomRenderElement<UdevRenderer<'a>>>,
+    space: &Space<crate::view::window::Window>,
+    session_lock: &SessionLockState,
+    output: &smithay::output::Output,
+    clock: &Clock<Monotonic>,
+) -> Result<bool, SwapBuffersError> {
+    let (elements, clear_color) =
+        output_elements(renderer, output, space, session_lock, custom_elements);
     let res =
         surface
             .compositor
@@ -1493,6 +1808,7 @@ fn render_surface<'a>(
         output,
         &res.states,
         space,
+        session_lock,
         surface
             .dmabuf_feedback
             .as_ref()
@@ -1586,6 +1902,23 @@ impl EventHandler<InputEvent<LibinputInputBackend>> for TatarajoState {
                     let _ = device.config_send_events_set_mode(libinput::SendEventsMode::ENABLED);
                 }
 
+                let config = LibinputDeviceConfig::resolve(
+                    &self.backend_udev().libinput_config,
+                    device.name(),
+                    &libinput_device_types(&device),
+                );
+                apply_libinput_device_config(&mut device, &config);
+                self.inner
+                    .input_device_configs
+                    .insert(smithay::backend::input::Device::id(&device), config);
+
+                if device.has_capability(libinput::DeviceCapability::TabletTool) {
+                    self.inner.seat.tablet_seat().add_tablet::<TatarajoState>(
+                        &self.inner.display_handle,
+                        &TabletDescriptor::from(&device),
+                    );
+                }
+
                 if device.has_capability(libinput::DeviceCapability::Keyboard) {
                     if let Some(led_state) = self
                         .inner
@@ -1608,6 +1941,17 @@ impl EventHandler<InputEvent<LibinputInputBackend>> for TatarajoState {
                     LibinputDeviceInfo(&device)
                 );
 
+                if device.has_capability(libinput::DeviceCapability::TabletTool) {
+                    let tablet_seat = self.inner.seat.tablet_seat();
+                    tablet_seat.remove_tablet(&TabletDescriptor::from(&device));
+                    if tablet_seat.count_tablets() == 0 {
+                        tablet_seat.clear_tools();
+                    }
+                }
+
+                self.inner
+                    .input_device_configs
+                    .remove(&smithay::backend::input::Device::id(&device));
                 self.as_udev_mut().backend.input_devices.remove(&device);
             }
             _ => {
@@ -1617,6 +1961,71 @@ impl EventHandler<InputEvent<LibinputInputBackend>> for TatarajoState {
     }
 }
 
+fn libinput_device_types(device: &libinput::Device) -> Vec<LibinputDeviceType> {
+    use libinput::DeviceCapability as C;
+
+    let mut types = vec![];
+    if device.has_capability(C::Keyboard) {
+        types.push(LibinputDeviceType::Keyboard);
+    }
+    if device.has_capability(C::Pointer) {
+        // libinput has no explicit notion of touchpads. Tapping is available only for them.
+        if device.config_tap_finger_count() > 0 {
+            types.push(LibinputDeviceType::Touchpad);
+        } else {
+            types.push(LibinputDeviceType::Pointer);
+        }
+    }
+    if device.has_capability(C::Touch) {
+        types.push(LibinputDeviceType::Touch);
+    }
+    if device.has_capability(C::TabletTool) || device.has_capability(C::TabletPad) {
+        types.push(LibinputDeviceType::Tablet);
+    }
+    types
+}
+
+fn apply_libinput_device_config(device: &mut libinput::Device, config: &LibinputDeviceConfig) {
+    fn log_err(name: &str, device: &libinput::Device, r: libinput::DeviceConfigResult) {
+        if let Err(e) = r {
+            warn!("failed to set {name} for {}: {:?}", device.name(), e);
+        }
+    }
+
+    if let Some(x) = config.tap {
+        let r = device.config_tap_set_enabled(x);
+        log_err("tap", device, r);
+    }
+    if let Some(x) = config.natural_scroll {
+        let r = device.config_scroll_set_natural_scroll_enabled(x);
+        log_err("natural_scroll", device, r);
+    }
+    if let Some(x) = config.disable_while_typing {
+        let r = device.config_dwt_set_enabled(x);
+        log_err("disable_while_typing", device, r);
+    }
+    if let Some(x) = config.accel_profile {
+        let profile = match x {
+            LibinputAccelProfile::Flat => libinput::AccelProfile::Flat,
+            LibinputAccelProfile::Adaptive => libinput::AccelProfile::Adaptive,
+        };
+        let r = device.config_accel_set_profile(profile);
+        log_err("accel_profile", device, r);
+    }
+    if let Some(x) = config.accel_speed {
+        let r = device.config_accel_set_speed(x);
+        log_err("accel_speed", device, r);
+    }
+    if let Some(x) = config.left_handed {
+        let r = device.config_left_handed_set(x);
+        log_err("left_handed", device, r);
+    }
+    if let Some(x) = config.middle_emulation {
+        let r = device.config_middle_emulation_set_enabled(x);
+        log_err("middle_emulation", device, r);
+    }
+}
+
 struct LibinputDeviceInfo<'a>(&'a libinput::Device);
 
 impl std::fmt::Debug for LibinputDeviceInfo<'_> {
@@ -1686,12 +2095,27 @@ impl EventHandler<smithay::backend::session::Event>
                     if let Some(lease_global) = backend.leasing_global.as_mut() {
                         lease_global.resume::<TatarajoState>();
                     }
-                    for surface in backend.surfaces.values_mut() {
+                    for (crtc, surface) in backend.surfaces.iter_mut() {
                         if let Err(err) = surface.compositor.reset_state() {
                             warn!("Failed to reset drm surface state: {}", err);
                         }
 
-                        surface.render_loop.start();
+                        // Outputs turned off by DPMS are kept off.
+                        let id = UdevOutputId {
+                            primary_node: surface.primary_node,
+                            crtc: *crtc,
+                        };
+                        let is_on = self
+                            .inner
+                            .space
+                            .outputs()
+                            .find(|o| o.user_data().get::<UdevOutputId>() == Some(&id))
+                            .map_or(true, |o| self.inner.output_power_state.is_on(o));
+                        if is_on {
+                            surface.render_loop.start();
+                        } else if let Err(err) = surface.compositor.clear() {
+                            warn!("Failed to turn off drm surface: {}", err);
+                        }
                     }
                 }
             }
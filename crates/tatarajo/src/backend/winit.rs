@@ -11,7 +11,7 @@ use eyre::WrapErr;
 use smithay::backend::egl::EGLDevice;
 use smithay::backend::renderer::damage::{Error as OutputDamageTrackerError, OutputDamageTracker};
 use smithay::backend::renderer::element::AsRenderElements;
-use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 #[cfg(feature = "egl")]
 use smithay::backend::renderer::ImportEgl;
 use smithay::backend::renderer::{ImportDma, ImportMemWl};
@@ -194,6 +194,20 @@ impl BackendI for WinitBackend {
     fn change_vt(&mut self, _vt: i32) {
         error!("changing VT is not supported on winit backend");
     }
+
+    fn reload_output_config(&mut self, _inner: &mut InnerState) -> eyre::Result<()> {
+        error!("output configuration is not supported on winit backend");
+        Ok(())
+    }
+
+    fn set_output_power(&mut self, _output: &smithay::output::Output, on: bool) {
+        if on {
+            self.full_redraw = 4;
+            self.render_loop.start();
+        } else {
+            self.render_loop.stop();
+        }
+    }
 }
 
 impl EventHandler<WinitEvent> for TatarajoState {
@@ -261,7 +275,7 @@ impl TatarajoStateWithConcreteBackend<'_, WinitBackend> {
 
         let full_redraw = &mut self.backend.full_redraw;
         *full_redraw = full_redraw.saturating_sub(1);
-        let space = &mut self.inner.space;
+        let space = &self.inner.space;
         let damage_tracker = &mut self.backend.damage_tracker;
 
         let dnd_icon = self.inner.dnd_icon.as_ref();
@@ -283,6 +297,7 @@ impl TatarajoStateWithConcreteBackend<'_, WinitBackend> {
         let cursor_pos = self.inner.pointer.current_location() - cursor_hotspot.to_f64();
         let cursor_pos_scaled = cursor_pos.to_physical(scale).to_i32_round();
 
+        let is_locked = self.inner.session_lock_state.is_locked();
         let render_res = self.backend.backend.bind().and_then(|_| {
             let age = if *full_redraw > 0 {
                 0
@@ -314,10 +329,13 @@ impl TatarajoStateWithConcreteBackend<'_, WinitBackend> {
                 }
             }
 
+            elements.extend(self.inner.overlay_elements(renderer, &self.backend.output));
+
             render_output(
                 renderer,
                 &self.backend.output,
                 space,
+                &self.inner.session_lock_state,
                 elements,
                 damage_tracker,
                 age,
@@ -331,11 +349,19 @@ impl TatarajoStateWithConcreteBackend<'_, WinitBackend> {
         match render_res {
             Ok(render_output_result) => {
                 let has_rendered = render_output_result.damage.is_some();
+                let mut submitted = true;
                 if let Some(damage) = render_output_result.damage {
                     if let Err(err) = self.backend.backend.submit(Some(&*damage)) {
                         warn!("Failed to submit buffer: {}", err);
+                        submitted = false;
                     }
                 }
+                if is_locked && submitted {
+                    // The last frame is still locked if nothing is damaged.
+                    self.inner
+                        .session_lock_state
+                        .on_locked_frame(&self.backend.output, &self.inner.space);
+                }
 
                 self.backend
                     .backend
@@ -348,6 +374,7 @@ impl TatarajoStateWithConcreteBackend<'_, WinitBackend> {
                     &self.backend.output,
                     &render_output_result.states,
                     &self.inner.space,
+                    &self.inner.session_lock_state,
                     None,
                     time.into(),
                 );
@@ -377,6 +404,27 @@ impl TatarajoStateWithConcreteBackend<'_, WinitBackend> {
             Err(err) => warn!("Rendering error: {}", err),
         }
 
+        drop(cursor_guard);
+        let pointer_element = &self.backend.pointer_element;
+        self.inner.render_captures::<_, GlesTexture, _>(
+            self.backend.backend.renderer(),
+            &self.backend.output,
+            |renderer, inner| {
+                let mut elements =
+                    pointer_element.render_elements(renderer, cursor_pos_scaled, scale, 1.0);
+                if let Some(surface) = inner.dnd_icon.as_ref().filter(|surface| surface.alive()) {
+                    elements.extend(AsRenderElements::<GlesRenderer>::render_elements(
+                        &smithay::desktop::space::SurfaceTree::from_surface(surface),
+                        renderer,
+                        cursor_pos_scaled,
+                        scale,
+                        1.0,
+                    ));
+                }
+                elements
+            },
+        );
+
         // TODO: Use `should_schedule_render = false` and call `on_vblank()` on frame callback.
         self.backend.render_loop.on_render_frame(true);
     }
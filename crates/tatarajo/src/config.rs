@@ -0,0 +1,81 @@
+use crate::input::{Key, KeySeq, ModMask, SwipeBinding};
+use crate::status_log::StatusLog;
+use crate::view::view::InsertPolicy;
+use std::path::PathBuf;
+use std::time::Duration;
+use xkbcommon::xkb::Keysym;
+
+/// How pointer events move the keyboard focus.
+///
+/// Clicking a window always focuses it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPolicy {
+    /// Only clicking changes focus.
+    Click,
+    /// Focus follows the pointer when it enters another window or moves more than
+    /// `Config::focus_distance_threshold`. It is kept when the pointer is on no window, and a window focused by keyboard
+    /// is not unfocused by a slight pointer movement.
+    Sloppy,
+    /// Focus follows any pointer movement, and nothing is focused when the pointer is on no window.
+    Strict,
+}
+
+/// Configuration of tatarajo.
+///
+/// Workspace tags and a keymap are given to `TatarajoState::run()` separately.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// A key that discards a pending key sequence, e.g. `C-g`. It must consist of one key.
+    pub keyseq_cancel: Option<KeySeq>,
+    /// A pending key sequence is discarded if no key is pressed for this duration.
+    pub keyseq_timeout: Option<Duration>,
+    /// Shows a pending key sequence and its continuations on screen.
+    pub keyseq_overlay: bool,
+    /// Touchpad swipe gestures bound to actions.
+    pub swipe_bindings: Vec<SwipeBinding>,
+    /// Distance fingers move to fire a swipe binding, in the unit of libinput's normalized delta.
+    pub swipe_threshold: f64,
+    pub focus_policy: FocusPolicy,
+    /// Distance the pointer moves in a window to focus it, for `FocusPolicy::Sloppy`.
+    pub focus_distance_threshold: f64,
+    /// Moves the pointer to the center of a window when it is focused by actions, e.g. `ActionMoveFocus`.
+    pub mouse_follows_focus: bool,
+    /// Where a new window is inserted in the stack.
+    pub insert_policy: InsertPolicy,
+    /// Shows an error of an action on screen for this duration. Errors are always logged.
+    pub error_notification_timeout: Option<Duration>,
+    /// Directory where stdout and stderr of spawned programs are written to, as `<program>.log`. If `None`, they are
+    /// inherited from tatarajo.
+    pub spawn_log_dir: Option<PathBuf>,
+    /// Windows of a program spawned by `Action::Spawn` are placed on the workspace it was spawned for, if they are
+    /// mapped within this duration.
+    pub spawn_workspace_timeout: Duration,
+    /// Writes workspaces, the layout name and the focused window title on every change, e.g. for a status bar.
+    pub status_log: Option<StatusLog>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keyseq_cancel: Some(
+                vec![Key {
+                    modmask: ModMask::default(),
+                    keysym: Keysym::Escape,
+                }]
+                .into(),
+            ),
+            keyseq_timeout: Some(Duration::from_secs(5)),
+            keyseq_overlay: true,
+            swipe_bindings: vec![],
+            swipe_threshold: 100.0,
+            focus_policy: FocusPolicy::Sloppy,
+            focus_distance_threshold: 16.0,
+            mouse_follows_focus: false,
+            insert_policy: InsertPolicy::End,
+            error_notification_timeout: Some(Duration::from_secs(5)),
+            spawn_log_dir: None,
+            spawn_workspace_timeout: Duration::from_secs(60),
+            status_log: None,
+        }
+    }
+}
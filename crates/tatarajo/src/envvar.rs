@@ -1,4 +1,7 @@
 use crate::backend::udev::SurfaceCompositionPolicy;
+use crate::util::glob::glob_match;
+use eyre::WrapErr;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug)]
@@ -22,18 +25,15 @@ pub(crate) struct EnvVarTatarajo {
     /// Both primary node (e.g. /dev/dri/card0) and render node (e.g. /dev/dri/renderD128) are
     /// available. Tatarajo infers corresponding primary/render nodes.
     pub drm_device_node: Option<PathBuf>,
-    #[serde(default = "default_bool::<false>")]
-    pub disable_10bit: bool,
     #[serde(default = "Default::default")]
     pub surface_composition_policy: SurfaceCompositionPolicy,
     #[serde(default = "Default::default")]
     pub xkb_config: Option<String>,
-}
-
-// https://github.com/serde-rs/serde/issues/1030
-// TODO(https://github.com/serde-rs/serde/issues/368): Use literal once default literals is supported.
-const fn default_bool<const V: bool>() -> bool {
-    V
+    #[serde(default = "Default::default")]
+    pub libinput_config: Option<String>,
+    /// Path to a JSON file of `OutputConfig` rules. It is read again by `ActionReloadOutputConfig`.
+    #[serde(default = "Default::default")]
+    pub output_config: Option<PathBuf>,
 }
 
 impl EnvVar {
@@ -44,19 +44,491 @@ impl EnvVar {
         })
     }
 
-    pub fn xkb_config(&self) -> eyre::Result<Option<XkbConfig>> {
-        self.tatarajo
+    /// Returns `XkbConfig::default()` if `TATARAJO_XKB_CONFIG` is not set.
+    pub fn xkb_config(&self) -> eyre::Result<XkbConfig> {
+        let config = self
+            .tatarajo
             .xkb_config
             .as_deref()
             .map(serde_json::from_str)
-            .transpose()
-            .map_err(|e| e.into())
+            .transpose()?
+            .unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Returns an empty list if `TATARAJO_LIBINPUT_CONFIG` is not set.
+    pub fn libinput_config(&self) -> eyre::Result<Vec<LibinputDeviceConfig>> {
+        let config = self
+            .tatarajo
+            .libinput_config
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?
+            .unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Reads the file given by `TATARAJO_OUTPUT_CONFIG`. Returns an empty list if it is not set.
+    pub fn output_config(&self) -> eyre::Result<Vec<OutputConfig>> {
+        let Some(path) = &self.tatarajo.output_config else {
+            return Ok(vec![]);
+        };
+        let content =
+            std::fs::read_to_string(path).wrap_err_with(|| format!("read {}", path.display()))?;
+        let config: Vec<OutputConfig> =
+            serde_json::from_str(&content).wrap_err_with(|| format!("parse {}", path.display()))?;
+        for rule in &config {
+            rule.validate()
+                .wrap_err_with(|| format!("validate {}", path.display()))?;
+        }
+        Ok(config)
     }
 }
 
+/// Configuration of keyboards, given by `TATARAJO_XKB_CONFIG` in JSON.
+///
+/// E.g. `{"layout": "us,de", "options": "ctrl:nocaps", "devices": {"Apple Keyboard": {"model": "apple"}}}`.
 #[derive(Debug, serde::Deserialize)]
 pub(crate) struct XkbConfig {
-    pub layout: String,
+    #[serde(flatten)]
+    pub keymap: XkbKeymapConfig,
+    #[serde(default = "default_repeat_delay")]
     pub repeat_delay: u16,
+    #[serde(default = "default_repeat_rate")]
     pub repeat_rate: u16,
+    /// Overrides per libinput device name. Fields not given are inherited from the above.
+    #[serde(default)]
+    pub devices: HashMap<String, XkbKeymapConfig>,
+}
+
+impl Default for XkbConfig {
+    fn default() -> Self {
+        Self {
+            keymap: XkbKeymapConfig::default(),
+            repeat_delay: default_repeat_delay(),
+            repeat_rate: default_repeat_rate(),
+            devices: HashMap::new(),
+        }
+    }
+}
+
+fn default_repeat_delay() -> u16 {
+    200
+}
+
+fn default_repeat_rate() -> u16 {
+    25
+}
+
+/// RMLVO names and options. Empty strings fall back to xkbcommon's defaults, e.g. `XKB_DEFAULT_LAYOUT`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct XkbKeymapConfig {
+    pub rules: String,
+    pub model: String,
+    /// Comma separated layouts, e.g. "us,de". `ActionSwitchKeyboardLayout` cycles them.
+    pub layout: String,
+    pub variant: String,
+    /// Comma separated options, e.g. "ctrl:nocaps,compose:ralt".
+    pub options: Option<String>,
+}
+
+impl XkbConfig {
+    /// Returns the keymap config for the device with the given name.
+    pub fn keymap_for_device(&self, device_name: &str) -> XkbKeymapConfig {
+        match self.devices.get(device_name) {
+            Some(x) => x.inherit(&self.keymap),
+            None => self.keymap.clone(),
+        }
+    }
+}
+
+impl XkbKeymapConfig {
+    fn inherit(&self, base: &XkbKeymapConfig) -> XkbKeymapConfig {
+        let choose = |x: &String, y: &String| if x.is_empty() { y.clone() } else { x.clone() };
+        XkbKeymapConfig {
+            rules: choose(&self.rules, &base.rules),
+            model: choose(&self.model, &base.model),
+            layout: choose(&self.layout, &base.layout),
+            variant: choose(&self.variant, &base.variant),
+            options: self.options.clone().or_else(|| base.options.clone()),
+        }
+    }
+
+    pub fn as_smithay(&self) -> smithay::input::keyboard::XkbConfig<'_> {
+        smithay::input::keyboard::XkbConfig {
+            rules: &self.rules,
+            model: &self.model,
+            layout: &self.layout,
+            variant: &self.variant,
+            options: self.options.clone(),
+        }
+    }
+}
+
+/// A rule of configuration of libinput devices. `TATARAJO_LIBINPUT_CONFIG` is a JSON array of them.
+///
+/// Rules are applied in order to devices matched with both `name` and `type`, and later ones override earlier
+/// ones. Settings not given or not supported by a device are left as is.
+///
+/// E.g. `[{"type": "touchpad", "tap": true, "natural_scroll": true}, {"name": "Logitech*", "scroll_factor": 0.5}]`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct LibinputDeviceConfig {
+    /// Glob pattern of device names, e.g. "*TouchPad*".
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: Option<LibinputDeviceType>,
+    pub tap: Option<bool>,
+    pub natural_scroll: Option<bool>,
+    pub disable_while_typing: Option<bool>,
+    pub accel_profile: Option<LibinputAccelProfile>,
+    /// In [-1.0, 1.0].
+    pub accel_speed: Option<f64>,
+    pub left_handed: Option<bool>,
+    pub middle_emulation: Option<bool>,
+    /// Multiplier of scroll amount.
+    pub scroll_factor: Option<f64>,
+    /// Name of the output to which absolute positions of touchscreens and tablets are mapped, e.g. "eDP-1".
+    /// Defaults to the first output.
+    pub map_to_output: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LibinputDeviceType {
+    Keyboard,
+    /// Pointer devices except touchpads.
+    Pointer,
+    Touchpad,
+    Touch,
+    Tablet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LibinputAccelProfile {
+    Flat,
+    Adaptive,
+}
+
+impl LibinputDeviceConfig {
+    /// Merges rules that match the device with the given name and types.
+    pub fn resolve(
+        rules: &[LibinputDeviceConfig],
+        device_name: &str,
+        device_types: &[LibinputDeviceType],
+    ) -> LibinputDeviceConfig {
+        let mut ret = LibinputDeviceConfig::default();
+        for rule in rules {
+            if let Some(pattern) = &rule.name {
+                if !glob_match(pattern, device_name) {
+                    continue;
+                }
+            }
+            if let Some(type_) = rule.type_ {
+                if !device_types.contains(&type_) {
+                    continue;
+                }
+            }
+
+            macro_rules! merge {
+                ($($field:ident),*) => {
+                    $(
+                        if rule.$field.is_some() {
+                            ret.$field = rule.$field;
+                        }
+                    )*
+                };
+            }
+            merge!(
+                tap,
+                natural_scroll,
+                disable_while_typing,
+                accel_profile,
+                accel_speed,
+                left_handed,
+                middle_emulation,
+                scroll_factor
+            );
+            if rule.map_to_output.is_some() {
+                ret.map_to_output = rule.map_to_output.clone();
+            }
+        }
+        ret
+    }
+}
+
+/// A rule of configuration of outputs. The file given by `TATARAJO_OUTPUT_CONFIG` contains a JSON array of them.
+///
+/// Rules are applied in order to outputs matched with all of `name`, `make`, `model` and `serial`, and later
+/// ones override earlier ones. Settings not given are chosen automatically.
+///
+/// E.g. `[{"name": "eDP-1", "scale": 1.5}, {"make": "Dell*", "mode": {"width": 2560, "height": 1440, "refresh": 144},
+/// "position": [1920, 0], "transform": "90"}]`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct OutputConfig {
+    /// Glob pattern of connector names, e.g. "HDMI-A-*".
+    pub name: Option<String>,
+    /// Glob pattern of the manufacturer in EDID.
+    pub make: Option<String>,
+    /// Glob pattern of the model in EDID.
+    pub model: Option<String>,
+    /// Glob pattern of the serial number in EDID. Outputs without a serial number don't match.
+    pub serial: Option<String>,
+    /// Disabled outputs are not used at all. Defaults to true.
+    pub enabled: Option<bool>,
+    /// Defaults to the preferred mode of the output.
+    pub mode: Option<OutputModeConfig>,
+    /// Integer or fractional scale. Defaults to a value estimated from DPI.
+    pub scale: Option<f64>,
+    pub transform: Option<OutputTransform>,
+    /// Top left corner in the global logical coordinate. Defaults to the right of the other outputs.
+    pub position: Option<[i32; 2]>,
+    /// Whether to use 10-bit color formats if available. Defaults to true.
+    pub prefer_10bit: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub(crate) struct OutputModeConfig {
+    pub width: u16,
+    pub height: u16,
+    /// In Hz. Defaults to the highest one for the size.
+    #[serde(default)]
+    pub refresh: Option<f64>,
+}
+
+/// Counterclockwise rotation, optionally after flipping around the vertical axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub(crate) enum OutputTransform {
+    #[serde(rename = "normal")]
+    Normal,
+    #[serde(rename = "90")]
+    _90,
+    #[serde(rename = "180")]
+    _180,
+    #[serde(rename = "270")]
+    _270,
+    #[serde(rename = "flipped")]
+    Flipped,
+    #[serde(rename = "flipped_90")]
+    Flipped90,
+    #[serde(rename = "flipped_180")]
+    Flipped180,
+    #[serde(rename = "flipped_270")]
+    Flipped270,
+}
+
+impl From<OutputTransform> for smithay::utils::Transform {
+    fn from(x: OutputTransform) -> Self {
+        use smithay::utils::Transform;
+
+        match x {
+            OutputTransform::Normal => Transform::Normal,
+            OutputTransform::_90 => Transform::_90,
+            OutputTransform::_180 => Transform::_180,
+            OutputTransform::_270 => Transform::_270,
+            OutputTransform::Flipped => Transform::Flipped,
+            OutputTransform::Flipped90 => Transform::Flipped90,
+            OutputTransform::Flipped180 => Transform::Flipped180,
+            OutputTransform::Flipped270 => Transform::Flipped270,
+        }
+    }
+}
+
+impl OutputConfig {
+    /// Merges rules that match the output with the given connector name and EDID information.
+    pub fn resolve(
+        rules: &[OutputConfig],
+        name: &str,
+        make: &str,
+        model: &str,
+        serial: Option<&str>,
+    ) -> OutputConfig {
+        let matches = |pattern: &Option<String>, value: Option<&str>| match (pattern, value) {
+            (None, _) => true,
+            (Some(pattern), Some(value)) => glob_match(pattern, value),
+            (Some(_), None) => false,
+        };
+
+        let mut ret = OutputConfig::default();
+        for rule in rules {
+            if !(matches(&rule.name, Some(name))
+                && matches(&rule.make, Some(make))
+                && matches(&rule.model, Some(model))
+                && matches(&rule.serial, serial))
+            {
+                continue;
+            }
+
+            macro_rules! merge {
+                ($($field:ident),*) => {
+                    $(
+                        if rule.$field.is_some() {
+                            ret.$field = rule.$field;
+                        }
+                    )*
+                };
+            }
+            merge!(enabled, mode, scale, transform, position, prefer_10bit);
+        }
+        ret
+    }
+
+    fn validate(&self) -> eyre::Result<()> {
+        if let Some(scale) = self.scale {
+            if !(scale.is_finite() && scale > 0.0) {
+                return Err(eyre::eyre!(
+                    "scale must be a positive number: scale = {}",
+                    scale
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn prefer_10bit(&self) -> bool {
+        self.prefer_10bit.unwrap_or(true)
+    }
+
+    pub fn scale(&self) -> Option<smithay::output::Scale> {
+        let scale = self.scale?;
+        if scale.fract() == 0.0 {
+            Some(smithay::output::Scale::Integer(scale as i32))
+        } else {
+            Some(smithay::output::Scale::Fractional(scale))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xkb_config_keymap_for_device() {
+        let config: XkbConfig = serde_json::from_str(
+            r#"{
+                "layout": "us,de",
+                "options": "ctrl:nocaps",
+                "devices": {
+                    "Apple Keyboard": {"model": "apple"},
+                    "HHKB": {"layout": "jp", "options": ""}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.repeat_delay, 200);
+        assert_eq!(config.repeat_rate, 25);
+
+        let keymap = config.keymap_for_device("AT Translated Set 2 keyboard");
+        assert_eq!(keymap, config.keymap);
+
+        let keymap = config.keymap_for_device("Apple Keyboard");
+        assert_eq!(keymap.model, "apple");
+        assert_eq!(keymap.layout, "us,de");
+        assert_eq!(keymap.options.as_deref(), Some("ctrl:nocaps"));
+
+        let keymap = config.keymap_for_device("HHKB");
+        assert_eq!(keymap.model, "");
+        assert_eq!(keymap.layout, "jp");
+        assert_eq!(keymap.options.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_libinput_device_config_resolve() {
+        let rules: Vec<LibinputDeviceConfig> = serde_json::from_str(
+            r#"[
+                {"type": "touchpad", "tap": true, "natural_scroll": true, "accel_profile": "adaptive"},
+                {"type": "pointer", "accel_profile": "flat"},
+                {"name": "*Synaptics*", "natural_scroll": false, "scroll_factor": 0.5}
+            ]"#,
+        )
+        .unwrap();
+
+        let config = LibinputDeviceConfig::resolve(
+            &rules,
+            "SynPS/2 Synaptics TouchPad",
+            &[LibinputDeviceType::Touchpad],
+        );
+        assert_eq!(config.tap, Some(true));
+        assert_eq!(config.natural_scroll, Some(false));
+        assert_eq!(config.accel_profile, Some(LibinputAccelProfile::Adaptive));
+        assert_eq!(config.scroll_factor, Some(0.5));
+        assert_eq!(config.left_handed, None);
+
+        let config = LibinputDeviceConfig::resolve(
+            &rules,
+            "Logitech USB Receiver",
+            &[LibinputDeviceType::Keyboard, LibinputDeviceType::Pointer],
+        );
+        assert_eq!(config.tap, None);
+        assert_eq!(config.accel_profile, Some(LibinputAccelProfile::Flat));
+        assert_eq!(config.scroll_factor, None);
+
+        let config = LibinputDeviceConfig::resolve(&rules, "AT Translated Set 2 keyboard", &[]);
+        assert_eq!(config, LibinputDeviceConfig::default());
+    }
+
+    #[test]
+    fn test_output_config_resolve() {
+        let rules: Vec<OutputConfig> = serde_json::from_str(
+            r#"[
+                {"scale": 1, "prefer_10bit": false},
+                {"name": "eDP-*", "scale": 1.5, "transform": "flipped_90"},
+                {"make": "Dell*", "model": "U2720Q", "mode": {"width": 3840, "height": 2160}, "position": [1920, 0]},
+                {"serial": "ABC123", "enabled": false}
+            ]"#,
+        )
+        .unwrap();
+
+        let config = OutputConfig::resolve(&rules, "eDP-1", "BOE", "0x0BCA", None);
+        assert_eq!(config.scale, Some(1.5));
+        assert_eq!(config.transform, Some(OutputTransform::Flipped90));
+        assert_eq!(config.position, None);
+        assert!(config.enabled());
+        assert!(!config.prefer_10bit());
+
+        let config = OutputConfig::resolve(&rules, "DP-1", "Dell Inc.", "U2720Q", Some("XYZ789"));
+        assert!(matches!(
+            config.scale(),
+            Some(smithay::output::Scale::Integer(1))
+        ));
+        assert_eq!(
+            config.mode,
+            Some(OutputModeConfig {
+                width: 3840,
+                height: 2160,
+                refresh: None
+            })
+        );
+        assert_eq!(config.position, Some([1920, 0]));
+        assert!(config.enabled());
+
+        let config = OutputConfig::resolve(&rules, "DP-2", "Dell Inc.", "U2720Q", Some("ABC123"));
+        assert!(!config.enabled());
+    }
+
+    #[test]
+    fn test_output_config_validate() {
+        let rules: Vec<OutputConfig> = serde_json::from_str(
+            r#"[
+                {"name": "eDP-1", "scale": 1.5},
+                {"name": "DP-1", "scale": 0},
+                {"name": "DP-2", "scale": -2},
+                {"name": "DP-3"}
+            ]"#,
+        )
+        .unwrap();
+        let results = rules
+            .iter()
+            .map(|rule| rule.validate().is_ok())
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec![true, false, false, true]);
+    }
 }
@@ -0,0 +1,333 @@
+//! `ext-workspace-v1`, which exposes workspaces of the `StackSet` to e.g. bars.
+//!
+//! All workspaces are in one group that contains all outputs, because the view spans them.
+
+use crate::action::{ActionFnI, ActionWorkspaceFocus};
+use crate::state::{ClientState, InnerState, TatarajoState};
+use crate::view::stackset::WorkspaceTag;
+use smithay::output::Output;
+use smithay::reexports::wayland_server::backend::{ClientId, ObjectId};
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use wayland_protocols::ext::workspace::v1::server::ext_workspace_group_handle_v1::{
+    self, ExtWorkspaceGroupHandleV1, GroupCapabilities,
+};
+use wayland_protocols::ext::workspace::v1::server::ext_workspace_handle_v1::{
+    self, ExtWorkspaceHandleV1, WorkspaceCapabilities,
+};
+use wayland_protocols::ext::workspace::v1::server::ext_workspace_manager_v1::{
+    self, ExtWorkspaceManagerV1,
+};
+
+const VERSION: u32 = 1;
+
+pub(crate) struct ExtWorkspaceState {
+    managers: Vec<Manager>,
+}
+
+/// Objects of a bound manager and what were sent to them.
+struct Manager {
+    manager: ExtWorkspaceManagerV1,
+    group: ExtWorkspaceGroupHandleV1,
+    outputs: Vec<WlOutput>,
+    workspaces: Vec<(WorkspaceInfo, ExtWorkspaceHandleV1)>,
+    /// Activate request waiting for commit.
+    pending_activate: Option<WorkspaceTag>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WorkspaceInfo {
+    tag: WorkspaceTag,
+    /// Index in the `StackSet`.
+    coordinate: u32,
+    state: ext_workspace_handle_v1::State,
+}
+
+pub(crate) struct WorkspaceHandleData {
+    manager_id: ObjectId,
+    tag: WorkspaceTag,
+}
+
+impl ExtWorkspaceState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        display_handle.create_global::<TatarajoState, ExtWorkspaceManagerV1, _>(VERSION, ());
+        Self { managers: vec![] }
+    }
+}
+
+impl InnerState {
+    /// Sends changes of workspaces and outputs to clients. Called whenever the `StackSet` may change.
+    pub(crate) fn refresh_ext_workspace(&mut self) {
+        if self.ext_workspace_state.managers.is_empty() {
+            return;
+        }
+
+        let infos = self.ext_workspace_infos();
+        let outputs = self.space.outputs().cloned().collect::<Vec<_>>();
+        for manager in &mut self.ext_workspace_state.managers {
+            manager.sync(&self.display_handle, &infos, &outputs, false);
+        }
+    }
+
+    fn ext_workspace_infos(&self) -> Vec<WorkspaceInfo> {
+        use ext_workspace_handle_v1::State;
+
+        let stackset = self.view.stackset();
+        let current = stackset.workspaces.focused_index();
+        stackset
+            .workspaces
+            .as_vec()
+            .iter()
+            .enumerate()
+            .map(|(i, ws)| {
+                let mut state = State::empty();
+                if i == current {
+                    state |= State::Active;
+                }
+                let urgent = ws
+                    .stack
+                    .as_vec()
+                    .iter()
+                    .any(|&wid| self.view.window(wid).is_some_and(|w| w.is_urgent()));
+                if urgent {
+                    state |= State::Urgent;
+                }
+                WorkspaceInfo {
+                    tag: ws.tag.clone(),
+                    coordinate: i as u32,
+                    state,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Manager {
+    /// Sends differences from the last sync. `force_done` is set on the initial sync.
+    fn sync(
+        &mut self,
+        display_handle: &DisplayHandle,
+        infos: &[WorkspaceInfo],
+        outputs: &[Output],
+        force_done: bool,
+    ) {
+        let Some(client) = self.manager.client() else {
+            return;
+        };
+        let mut changed = force_done;
+
+        let wl_outputs = outputs
+            .iter()
+            .flat_map(|output| output.client_outputs(&client))
+            .collect::<Vec<_>>();
+        for wl_output in &wl_outputs {
+            if !self.outputs.contains(wl_output) {
+                self.group.output_enter(wl_output);
+                changed = true;
+            }
+        }
+        for wl_output in &self.outputs {
+            if !wl_outputs.contains(wl_output) && wl_output.is_alive() {
+                self.group.output_leave(wl_output);
+                changed = true;
+            }
+        }
+        self.outputs = wl_outputs;
+
+        let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.workspaces)
+            .into_iter()
+            .partition(|(sent, _)| infos.iter().any(|info| info.tag == sent.tag));
+        for (_, handle) in removed {
+            if handle.is_alive() {
+                self.group.workspace_leave(&handle);
+                handle.removed();
+            }
+            changed = true;
+        }
+        self.workspaces = kept;
+
+        for info in infos {
+            if let Some((sent, handle)) =
+                self.workspaces.iter_mut().find(|(s, _)| s.tag == info.tag)
+            {
+                // Skip handles destroyed by the client.
+                if handle.is_alive() && sent.coordinate != info.coordinate {
+                    handle.coordinates(coordinates(info));
+                    changed = true;
+                }
+                if handle.is_alive() && sent.state != info.state {
+                    handle.state(info.state);
+                    changed = true;
+                }
+                *sent = info.clone();
+                continue;
+            }
+
+            let data = WorkspaceHandleData {
+                manager_id: self.manager.id(),
+                tag: info.tag.clone(),
+            };
+            let handle = match client.create_resource::<ExtWorkspaceHandleV1, _, TatarajoState>(
+                display_handle,
+                self.manager.version(),
+                data,
+            ) {
+                Ok(handle) => handle,
+                Err(err) => {
+                    warn!("failed to create ext_workspace_handle_v1: {}", err);
+                    continue;
+                }
+            };
+            self.manager.workspace(&handle);
+            handle.id(info.tag.to_string());
+            handle.name(info.tag.to_string());
+            handle.coordinates(coordinates(info));
+            handle.capabilities(WorkspaceCapabilities::Activate);
+            handle.state(info.state);
+            self.group.workspace_enter(&handle);
+            self.workspaces.push((info.clone(), handle));
+            changed = true;
+        }
+
+        if changed {
+            self.manager.done();
+        }
+    }
+}
+
+fn coordinates(info: &WorkspaceInfo) -> Vec<u8> {
+    info.coordinate.to_ne_bytes().to_vec()
+}
+
+impl GlobalDispatch<ExtWorkspaceManagerV1, ()> for TatarajoState {
+    fn bind(
+        state: &mut Self,
+        display_handle: &DisplayHandle,
+        client: &Client,
+        resource: New<ExtWorkspaceManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+        let group = match client.create_resource::<ExtWorkspaceGroupHandleV1, _, Self>(
+            display_handle,
+            manager.version(),
+            (),
+        ) {
+            Ok(group) => group,
+            Err(err) => {
+                warn!("failed to create ext_workspace_group_handle_v1: {}", err);
+                return;
+            }
+        };
+        manager.workspace_group(&group);
+        group.capabilities(GroupCapabilities::empty());
+
+        let mut manager = Manager {
+            manager,
+            group,
+            outputs: vec![],
+            workspaces: vec![],
+            pending_activate: None,
+        };
+        let infos = state.inner.ext_workspace_infos();
+        let outputs = state.inner.space.outputs().cloned().collect::<Vec<_>>();
+        manager.sync(display_handle, &infos, &outputs, true);
+        state.inner.ext_workspace_state.managers.push(manager);
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        client
+            .get_data::<ClientState>()
+            .map_or(true, |client_state| client_state.security_context.is_none())
+    }
+}
+
+impl Dispatch<ExtWorkspaceManagerV1, ()> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtWorkspaceManagerV1,
+        request: ext_workspace_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        let managers = &mut state.inner.ext_workspace_state.managers;
+        match request {
+            ext_workspace_manager_v1::Request::Commit => {
+                let tag = managers
+                    .iter_mut()
+                    .find(|m| m.manager == *resource)
+                    .and_then(|m| m.pending_activate.take());
+                if let Some(tag) = tag {
+                    state.process_action(&ActionWorkspaceFocus::WithTag(tag).into_action());
+                }
+            }
+            ext_workspace_manager_v1::Request::Stop => {
+                managers.retain(|m| m.manager != *resource);
+                resource.finished();
+            }
+            _ => {}
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ExtWorkspaceManagerV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .ext_workspace_state
+            .managers
+            .retain(|m| m.manager != *resource);
+    }
+}
+
+impl Dispatch<ExtWorkspaceGroupHandleV1, ()> for TatarajoState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtWorkspaceGroupHandleV1,
+        _request: ext_workspace_group_handle_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // `create_workspace` is not advertised in capabilities, and `destroy` is a destructor.
+    }
+}
+
+impl Dispatch<ExtWorkspaceHandleV1, WorkspaceHandleData> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ExtWorkspaceHandleV1,
+        request: ext_workspace_handle_v1::Request,
+        data: &WorkspaceHandleData,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_workspace_handle_v1::Request::Activate => {
+                let managers = &mut state.inner.ext_workspace_state.managers;
+                if let Some(manager) = managers
+                    .iter_mut()
+                    .find(|m| m.manager.id() == data.manager_id)
+                {
+                    manager.pending_activate = Some(data.tag.clone());
+                }
+            }
+            // Not advertised in capabilities.
+            ext_workspace_handle_v1::Request::Deactivate
+            | ext_workspace_handle_v1::Request::Assign { .. }
+            | ext_workspace_handle_v1::Request::Remove => {}
+            // `destroy` is a destructor.
+            _ => {}
+        }
+    }
+}
@@ -12,6 +12,7 @@ pub enum KeyboardFocusTarget {
     Window(smithay::desktop::Window),
     LayerSurface(smithay::desktop::LayerSurface),
     Popup(smithay::desktop::PopupKind),
+    LockSurface(WlSurface),
 }
 
 impl From<smithay::desktop::Window> for KeyboardFocusTarget {
@@ -45,6 +46,7 @@ impl smithay::utils::IsAlive for KeyboardFocusTarget {}
             }
             Self::LayerSurface(l) => f(l.wl_surface()),
             Self::Popup(p) => f(p.wl_surface()),
+            Self::LockSurface(s) => f(s),
         }
     }
 )]
@@ -56,6 +58,7 @@ impl smithay::wayland::seat::WaylandFocus for KeyboardFocusTarget {
             KeyboardFocusTarget::Window(w) => w.wl_surface(),
             KeyboardFocusTarget::LayerSurface(l) => Some(l.wl_surface().clone()),
             KeyboardFocusTarget::Popup(p) => Some(p.wl_surface().clone()),
+            KeyboardFocusTarget::LockSurface(s) => Some(s.clone()),
         }
     }
 }
@@ -112,6 +115,7 @@ impl From<KeyboardFocusTarget> for PointerFocusTarget {
             },
             KeyboardFocusTarget::LayerSurface(l) => PointerFocusTarget::from(l.wl_surface()),
             KeyboardFocusTarget::Popup(p) => PointerFocusTarget::from(p.wl_surface()),
+            KeyboardFocusTarget::LockSurface(s) => PointerFocusTarget::from(s),
         }
     }
 }
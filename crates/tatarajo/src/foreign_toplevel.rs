@@ -0,0 +1,463 @@
+//! `wlr-foreign-toplevel-management-unstable-v1` and `ext-foreign-toplevel-list-v1`, which publish windows of the
+//! view to e.g. taskbars.
+//!
+//! Windows on workspaces other than the focused one are reported as minimized, like `_NET_WM_STATE_HIDDEN` of
+//! xmonad's EWMH.
+
+use crate::action::{ActionFnI, ActionFocusWindow};
+use crate::state::{ClientState, InnerState, TatarajoState};
+use crate::util::Id;
+use crate::view::window::Window;
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_handle_v1::{
+    self, ZwlrForeignToplevelHandleV1,
+};
+use smithay::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_manager_v1::{
+    self, ZwlrForeignToplevelManagerV1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use std::collections::HashMap;
+use wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_handle_v1::{
+    self, ExtForeignToplevelHandleV1,
+};
+use wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_list_v1::{
+    self, ExtForeignToplevelListV1,
+};
+
+const WLR_VERSION: u32 = 3;
+const EXT_VERSION: u32 = 1;
+
+pub(crate) struct ForeignToplevelState {
+    wlr_managers: Vec<ZwlrForeignToplevelManagerV1>,
+    ext_lists: Vec<ExtForeignToplevelListV1>,
+    toplevels: HashMap<Id<Window>, Toplevel>,
+}
+
+/// A window and handles of it, with what were sent to them.
+struct Toplevel {
+    info: ToplevelInfo,
+    wlr_handles: Vec<ZwlrForeignToplevelHandleV1>,
+    ext_handles: Vec<ExtForeignToplevelHandleV1>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ToplevelInfo {
+    title: Option<String>,
+    app_id: Option<String>,
+    activated: bool,
+    minimized: bool,
+    outputs: Vec<Output>,
+}
+
+impl ForeignToplevelState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        display_handle
+            .create_global::<TatarajoState, ZwlrForeignToplevelManagerV1, _>(WLR_VERSION, ());
+        display_handle.create_global::<TatarajoState, ExtForeignToplevelListV1, _>(EXT_VERSION, ());
+        Self {
+            wlr_managers: vec![],
+            ext_lists: vec![],
+            toplevels: HashMap::new(),
+        }
+    }
+}
+
+impl InnerState {
+    /// Sends changes of windows to clients. Called whenever windows or the focus may change.
+    pub(crate) fn refresh_foreign_toplevel(&mut self) {
+        if self.foreign_toplevel_state.wlr_managers.is_empty()
+            && self.foreign_toplevel_state.ext_lists.is_empty()
+        {
+            // Toplevels are created again for the next binding.
+            self.foreign_toplevel_state.toplevels.clear();
+            return;
+        }
+
+        let infos = self.foreign_toplevel_infos();
+        let display_handle = &self.display_handle;
+        let state = &mut self.foreign_toplevel_state;
+
+        state.toplevels.retain(|window_id, toplevel| {
+            if infos.contains_key(window_id) {
+                return true;
+            }
+            for handle in &toplevel.wlr_handles {
+                handle.closed();
+            }
+            for handle in &toplevel.ext_handles {
+                handle.closed();
+            }
+            false
+        });
+
+        for (window_id, info) in infos {
+            if let Some(toplevel) = state.toplevels.get_mut(&window_id) {
+                toplevel.update(info);
+                continue;
+            }
+
+            let mut toplevel = Toplevel {
+                info,
+                wlr_handles: vec![],
+                ext_handles: vec![],
+            };
+            for manager in &state.wlr_managers {
+                toplevel.add_wlr_handle(display_handle, manager, window_id);
+            }
+            for list in &state.ext_lists {
+                toplevel.add_ext_handle(display_handle, list, window_id);
+            }
+            state.toplevels.insert(window_id, toplevel);
+        }
+    }
+
+    fn foreign_toplevel_infos(&self) -> HashMap<Id<Window>, ToplevelInfo> {
+        let focused_id = self.view.focused_window().map(|w| w.id());
+        let focused_ws = self.view.stackset().workspaces.focus();
+        self.view
+            .windows()
+            .map(|window| {
+                let info = ToplevelInfo {
+                    title: window.title(),
+                    app_id: window.app_id(),
+                    activated: Some(window.id()) == focused_id,
+                    minimized: !focused_ws.stack.as_vec().contains(&window.id()),
+                    outputs: self.space.outputs_for_element(window),
+                };
+                (window.id(), info)
+            })
+            .collect()
+    }
+
+    /// Sends `output_enter` for a `wl_output` bound after handles are created.
+    pub(crate) fn on_foreign_toplevel_output_bound(
+        &mut self,
+        output: &Output,
+        wl_output: &WlOutput,
+    ) {
+        for toplevel in self.foreign_toplevel_state.toplevels.values() {
+            if !toplevel.info.outputs.contains(output) {
+                continue;
+            }
+            for handle in &toplevel.wlr_handles {
+                if handle.client() == wl_output.client() {
+                    handle.output_enter(wl_output);
+                    handle.done();
+                }
+            }
+        }
+    }
+}
+
+impl Toplevel {
+    fn add_wlr_handle(
+        &mut self,
+        display_handle: &DisplayHandle,
+        manager: &ZwlrForeignToplevelManagerV1,
+        window_id: Id<Window>,
+    ) {
+        let Some(client) = manager.client() else {
+            return;
+        };
+        let handle = match client.create_resource::<ZwlrForeignToplevelHandleV1, _, TatarajoState>(
+            display_handle,
+            manager.version(),
+            window_id,
+        ) {
+            Ok(handle) => handle,
+            Err(err) => {
+                warn!("failed to create zwlr_foreign_toplevel_handle_v1: {}", err);
+                return;
+            }
+        };
+        manager.toplevel(&handle);
+
+        let info = &self.info;
+        if let Some(title) = &info.title {
+            handle.title(title.clone());
+        }
+        if let Some(app_id) = &info.app_id {
+            handle.app_id(app_id.clone());
+        }
+        for output in &info.outputs {
+            for wl_output in output.client_outputs(&client) {
+                handle.output_enter(&wl_output);
+            }
+        }
+        handle.state(wlr_states(info));
+        handle.done();
+        self.wlr_handles.push(handle);
+    }
+
+    fn add_ext_handle(
+        &mut self,
+        display_handle: &DisplayHandle,
+        list: &ExtForeignToplevelListV1,
+        window_id: Id<Window>,
+    ) {
+        let Some(client) = list.client() else {
+            return;
+        };
+        let handle = match client.create_resource::<ExtForeignToplevelHandleV1, _, TatarajoState>(
+            display_handle,
+            list.version(),
+            window_id,
+        ) {
+            Ok(handle) => handle,
+            Err(err) => {
+                warn!("failed to create ext_foreign_toplevel_handle_v1: {}", err);
+                return;
+            }
+        };
+        list.toplevel(&handle);
+
+        handle.identifier(window_id.to_string());
+        if let Some(title) = &self.info.title {
+            handle.title(title.clone());
+        }
+        if let Some(app_id) = &self.info.app_id {
+            handle.app_id(app_id.clone());
+        }
+        handle.done();
+        self.ext_handles.push(handle);
+    }
+
+    /// Sends differences from the last info.
+    fn update(&mut self, info: ToplevelInfo) {
+        self.wlr_handles.retain(|handle| handle.is_alive());
+        self.ext_handles.retain(|handle| handle.is_alive());
+        if self.info == info {
+            return;
+        }
+
+        let old = std::mem::replace(&mut self.info, info);
+        let new = &self.info;
+        let title = (old.title != new.title).then(|| new.title.clone().unwrap_or_default());
+        let app_id = (old.app_id != new.app_id).then(|| new.app_id.clone().unwrap_or_default());
+
+        for handle in &self.wlr_handles {
+            if let Some(title) = &title {
+                handle.title(title.clone());
+            }
+            if let Some(app_id) = &app_id {
+                handle.app_id(app_id.clone());
+            }
+            if let Some(client) = handle.client() {
+                for output in old.outputs.iter().filter(|o| !new.outputs.contains(o)) {
+                    for wl_output in output.client_outputs(&client) {
+                        handle.output_leave(&wl_output);
+                    }
+                }
+                for output in new.outputs.iter().filter(|o| !old.outputs.contains(o)) {
+                    for wl_output in output.client_outputs(&client) {
+                        handle.output_enter(&wl_output);
+                    }
+                }
+            }
+            if (old.activated, old.minimized) != (new.activated, new.minimized) {
+                handle.state(wlr_states(new));
+            }
+            handle.done();
+        }
+
+        if title.is_none() && app_id.is_none() {
+            return;
+        }
+        for handle in &self.ext_handles {
+            if let Some(title) = &title {
+                handle.title(title.clone());
+            }
+            if let Some(app_id) = &app_id {
+                handle.app_id(app_id.clone());
+            }
+            handle.done();
+        }
+    }
+}
+
+/// Returns the array of `zwlr_foreign_toplevel_handle_v1::state`.
+fn wlr_states(info: &ToplevelInfo) -> Vec<u8> {
+    use zwlr_foreign_toplevel_handle_v1::State;
+
+    let mut states = vec![];
+    if info.activated {
+        states.push(State::Activated);
+    }
+    if info.minimized {
+        states.push(State::Minimized);
+    }
+    states
+        .into_iter()
+        .flat_map(|state| (state as u32).to_ne_bytes())
+        .collect()
+}
+
+fn can_view(client: &Client) -> bool {
+    client
+        .get_data::<ClientState>()
+        .map_or(true, |client_state| client_state.security_context.is_none())
+}
+
+impl GlobalDispatch<ZwlrForeignToplevelManagerV1, ()> for TatarajoState {
+    fn bind(
+        state: &mut Self,
+        display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrForeignToplevelManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+        let ft_state = &mut state.inner.foreign_toplevel_state;
+        for (&window_id, toplevel) in &mut ft_state.toplevels {
+            toplevel.add_wlr_handle(display_handle, &manager, window_id);
+        }
+        ft_state.wlr_managers.push(manager);
+        // Creates the other toplevels and sends changes since the last refresh.
+        state.inner.refresh_foreign_toplevel();
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrForeignToplevelManagerV1,
+        request: zwlr_foreign_toplevel_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Request::Stop = request {
+            state
+                .inner
+                .foreign_toplevel_state
+                .wlr_managers
+                .retain(|m| m != resource);
+            resource.finished();
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrForeignToplevelManagerV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .foreign_toplevel_state
+            .wlr_managers
+            .retain(|m| m != resource);
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, Id<Window>> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ZwlrForeignToplevelHandleV1,
+        request: zwlr_foreign_toplevel_handle_v1::Request,
+        window_id: &Id<Window>,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            // Focusing a window on another workspace switches to it, which "unminimizes" the window.
+            zwlr_foreign_toplevel_handle_v1::Request::Activate { .. }
+            | zwlr_foreign_toplevel_handle_v1::Request::UnsetMinimized => {
+                state.process_action(&ActionFocusWindow(*window_id).into_action());
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::Close => {
+                if let Some(window) = state.inner.view.window(*window_id) {
+                    window.close();
+                }
+            }
+            // Windows have no minimized, maximized or fullscreen state of their own; they are always tiled.
+            request => {
+                debug!("ignored foreign toplevel request: {:?}", request);
+            }
+        }
+    }
+}
+
+impl GlobalDispatch<ExtForeignToplevelListV1, ()> for TatarajoState {
+    fn bind(
+        state: &mut Self,
+        display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtForeignToplevelListV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let list = data_init.init(resource, ());
+        let ft_state = &mut state.inner.foreign_toplevel_state;
+        for (&window_id, toplevel) in &mut ft_state.toplevels {
+            toplevel.add_ext_handle(display_handle, &list, window_id);
+        }
+        ft_state.ext_lists.push(list);
+        // Creates the other toplevels and sends changes since the last refresh.
+        state.inner.refresh_foreign_toplevel();
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ExtForeignToplevelListV1, ()> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtForeignToplevelListV1,
+        request: ext_foreign_toplevel_list_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // `destroy` is a destructor.
+        if let ext_foreign_toplevel_list_v1::Request::Stop = request {
+            state
+                .inner
+                .foreign_toplevel_state
+                .ext_lists
+                .retain(|l| l != resource);
+            resource.finished();
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ExtForeignToplevelListV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .foreign_toplevel_state
+            .ext_lists
+            .retain(|l| l != resource);
+    }
+}
+
+impl Dispatch<ExtForeignToplevelHandleV1, Id<Window>> for TatarajoState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtForeignToplevelHandleV1,
+        _request: ext_foreign_toplevel_handle_v1::Request,
+        _data: &Id<Window>,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // `destroy` is the only request, which is a destructor.
+    }
+}
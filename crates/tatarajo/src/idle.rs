@@ -0,0 +1,79 @@
+//! `ext-idle-notify-v1` and `idle-inhibit-unstable-v1`.
+//!
+//! Input activity is reported in `TatarajoState::process_input_event()`. Idle is inhibited while an inhibiting
+//! surface is visible, i.e. it belongs to a window in the `Space` or a layer surface.
+
+use crate::state::{InnerState, TatarajoState};
+use smithay::desktop::{layer_map_for_output, WindowSurfaceType};
+use smithay::reexports::calloop::LoopHandle;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{DisplayHandle, Resource};
+use smithay::wayland::compositor::get_parent;
+use smithay::wayland::idle_inhibit::IdleInhibitManagerState;
+use smithay::wayland::idle_notify::IdleNotifierState;
+use smithay::wayland::seat::WaylandFocus;
+
+pub(crate) struct IdleState {
+    pub notifier_state: IdleNotifierState<TatarajoState>,
+    inhibiting_surfaces: Vec<WlSurface>,
+}
+
+impl IdleState {
+    pub fn new(
+        display_handle: &DisplayHandle,
+        loop_handle: LoopHandle<'static, TatarajoState>,
+    ) -> Self {
+        IdleInhibitManagerState::new::<TatarajoState>(display_handle);
+        Self {
+            notifier_state: IdleNotifierState::new(display_handle, loop_handle),
+            inhibiting_surfaces: vec![],
+        }
+    }
+}
+
+impl InnerState {
+    pub(crate) fn add_idle_inhibitor(&mut self, surface: WlSurface) {
+        if !self.idle_state.inhibiting_surfaces.contains(&surface) {
+            self.idle_state.inhibiting_surfaces.push(surface);
+        }
+        self.refresh_idle_inhibit();
+    }
+
+    pub(crate) fn remove_idle_inhibitor(&mut self, surface: &WlSurface) {
+        self.idle_state.inhibiting_surfaces.retain(|s| s != surface);
+        self.refresh_idle_inhibit();
+    }
+
+    /// Inhibits idle iff an inhibiting surface is visible. Called whenever visibility of windows may change.
+    pub(crate) fn refresh_idle_inhibit(&mut self) {
+        self.idle_state
+            .inhibiting_surfaces
+            .retain(|surface| surface.is_alive());
+
+        let inhibited = !self.session_lock_state.is_locked()
+            && self
+                .idle_state
+                .inhibiting_surfaces
+                .iter()
+                .any(|surface| self.is_surface_visible(surface));
+        self.idle_state.notifier_state.set_is_inhibited(inhibited);
+    }
+
+    fn is_surface_visible(&self, surface: &WlSurface) -> bool {
+        let mut root = surface.clone();
+        while let Some(parent) = get_parent(&root) {
+            root = parent;
+        }
+
+        let in_window = self
+            .space
+            .elements()
+            .any(|window| window.smithay_window().wl_surface().as_ref() == Some(&root));
+        in_window
+            || self.space.outputs().any(|output| {
+                layer_map_for_output(output)
+                    .layer_for_surface(&root, WindowSurfaceType::TOPLEVEL)
+                    .is_some()
+            })
+    }
+}
@@ -0,0 +1,61 @@
+use crate::action::Action;
+use crate::input::{Key, KeySeq};
+use crate::state::TatarajoState;
+use smithay::input::keyboard::{FilterResult, KeysymHandle};
+
+/// Takes key presses in place of the keymap while it is active, e.g. the window picker.
+pub(crate) trait KeyCapture {
+    fn on_key(&mut self, key: &Key) -> KeyCaptureResult;
+
+    /// Lines shown in the overlay.
+    fn lines(&self) -> Vec<String>;
+}
+
+pub(crate) enum KeyCaptureResult {
+    Continue,
+    /// Ends the capture and executes the action if any.
+    Finish(Option<Action>),
+}
+
+impl TatarajoState {
+    /// Starts to capture keys. It replaces the current capture if any.
+    pub(crate) fn start_key_capture(&mut self, capture: Box<dyn KeyCapture>) {
+        self.inner.key_capture_overlay.set_lines(capture.lines());
+        self.inner.key_capture = Some(capture);
+    }
+
+    pub(crate) fn finish_key_capture(&mut self) {
+        self.inner.key_capture = None;
+        self.inner.key_capture_overlay.clear();
+    }
+
+    /// Handles a key press while a capture is active. `Config::keyseq_cancel` cancels the capture.
+    pub(crate) fn on_key_captured(
+        &mut self,
+        keysym_handle: &KeysymHandle<'_>,
+    ) -> FilterResult<Option<Action>> {
+        for key in KeySeq::extract(keysym_handle).into_vec() {
+            if self.is_keyseq_cancel(&key) {
+                debug!("key capture cancelled");
+                self.finish_key_capture();
+                return FilterResult::Intercept(None);
+            }
+
+            let Some(capture) = self.inner.key_capture.as_mut() else {
+                break;
+            };
+            match capture.on_key(&key) {
+                KeyCaptureResult::Continue => {}
+                KeyCaptureResult::Finish(action) => {
+                    self.finish_key_capture();
+                    return FilterResult::Intercept(action);
+                }
+            }
+        }
+
+        if let Some(capture) = &self.inner.key_capture {
+            self.inner.key_capture_overlay.set_lines(capture.lines());
+        }
+        FilterResult::Intercept(None)
+    }
+}
@@ -0,0 +1,125 @@
+use crate::action::{self, Action, ActionFnI};
+use smithay::utils::{Logical, Point};
+
+/// Direction of a swipe gesture, i.e. the direction fingers move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Runs `action` when fingers move `Config::swipe_threshold` in `direction`.
+///
+/// Swipes with the number of fingers used in some bindings are not sent to clients.
+#[derive(Debug, Clone)]
+pub struct SwipeBinding {
+    pub fingers: u32,
+    pub direction: SwipeDirection,
+    pub action: Action,
+}
+
+impl SwipeBinding {
+    /// Default bindings: swiping three fingers focuses the next or previous non-empty workspace, and swiping four
+    /// fingers moves the focused window there.
+    pub fn defaults() -> Vec<SwipeBinding> {
+        vec![
+            SwipeBinding {
+                fingers: 3,
+                direction: SwipeDirection::Left,
+                action: action::ActionWorkspaceFocusNonEmpty::Next.into_action(),
+            },
+            SwipeBinding {
+                fingers: 3,
+                direction: SwipeDirection::Right,
+                action: action::ActionWorkspaceFocusNonEmpty::Prev.into_action(),
+            },
+            SwipeBinding {
+                fingers: 4,
+                direction: SwipeDirection::Left,
+                action: action::ActionWindowMoveToWorkspace::Next.into_action(),
+            },
+            SwipeBinding {
+                fingers: 4,
+                direction: SwipeDirection::Right,
+                action: action::ActionWindowMoveToWorkspace::Prev.into_action(),
+            },
+        ]
+    }
+}
+
+/// Tracks a swipe gesture intercepted by tatarajo.
+#[derive(Debug)]
+pub(crate) struct SwipeTracker {
+    fingers: u32,
+    /// Accumulated delta since the beginning or the last firing.
+    delta: Point<f64, Logical>,
+}
+
+impl SwipeTracker {
+    pub fn new(fingers: u32) -> Self {
+        Self {
+            fingers,
+            delta: (0.0, 0.0).into(),
+        }
+    }
+
+    pub fn fingers(&self) -> u32 {
+        self.fingers
+    }
+
+    /// Accumulates `delta` and returns a direction if the movement exceeds `threshold`.
+    ///
+    /// The accumulated delta is reset when it fires, so that a long swipe fires repeatedly.
+    pub fn update(&mut self, delta: Point<f64, Logical>, threshold: f64) -> Option<SwipeDirection> {
+        self.delta += delta;
+
+        let (x, y) = (self.delta.x, self.delta.y);
+        let direction = if x.abs() >= y.abs() && x.abs() >= threshold {
+            if x < 0.0 {
+                SwipeDirection::Left
+            } else {
+                SwipeDirection::Right
+            }
+        } else if y.abs() > x.abs() && y.abs() >= threshold {
+            if y < 0.0 {
+                SwipeDirection::Up
+            } else {
+                SwipeDirection::Down
+            }
+        } else {
+            return None;
+        };
+
+        self.delta = (0.0, 0.0).into();
+        Some(direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swipe_tracker() {
+        let mut tracker = SwipeTracker::new(3);
+        assert_eq!(tracker.update((-40.0, 5.0).into(), 100.0), None);
+        assert_eq!(tracker.update((-40.0, 5.0).into(), 100.0), None);
+        assert_eq!(
+            tracker.update((-40.0, 5.0).into(), 100.0),
+            Some(SwipeDirection::Left)
+        );
+        assert_eq!(tracker.update((-40.0, 5.0).into(), 100.0), None);
+
+        let mut tracker = SwipeTracker::new(4);
+        assert_eq!(
+            tracker.update((30.0, 120.0).into(), 100.0),
+            Some(SwipeDirection::Down)
+        );
+        assert_eq!(
+            tracker.update((150.0, -20.0).into(), 100.0),
+            Some(SwipeDirection::Right)
+        );
+    }
+}
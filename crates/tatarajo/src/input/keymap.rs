@@ -1,4 +1,5 @@
-use super::keyseq::{KeySeq, KeySeqWithoutShiftMask};
+use super::keyseq::{Key, KeySeq, KeySeqWithoutShiftMask};
+use itertools::Itertools;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -36,6 +37,21 @@ where
         let keyseq = keyseq.clone().into();
         self.0.get(&keyseq).unwrap_or(&KeymapEntry::None)
     }
+
+    /// Returns keys that can follow `keyseq` and the entries they reach, sorted by keys' representation.
+    pub fn continuations(&self, keyseq: &KeySeq) -> Vec<(Key, &KeymapEntry<T>)> {
+        let prefix = KeySeqWithoutShiftMask::from(keyseq.clone());
+        let prefix = prefix.as_keyseq().as_keys();
+        let n = prefix.len();
+        self.0
+            .iter()
+            .filter_map(|(k, entry)| {
+                let keys = k.as_keyseq().as_keys();
+                (keys.len() == n + 1 && keys[..n] == prefix[..]).then(|| (keys[n].clone(), entry))
+            })
+            .sorted_by_key(|(key, _)| key.to_string())
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -179,4 +195,43 @@ mod tests {
         .into();
         assert_eq!(*keymap.get(&keyseq), KeymapEntry::None);
     }
+
+    #[test]
+    fn test_continuations() {
+        let keyseq_serde = KeySeqSerde::new(hashmap! {
+            S("C") => ModMask::CONTROL,
+            S("H") => ModMask::MOD5,
+        });
+        let kbd = |s| keyseq_serde.kbd(s).unwrap();
+        let keymap = Keymap::new(hashmap! {
+            kbd("H-t") => "next",
+            kbd("H-x H-t") => "alacritty",
+            kbd("H-x H-e") => "emacs",
+            kbd("H-x C-x H-q") => "quit",
+        });
+
+        let continuations = keymap
+            .continuations(&kbd("H-x"))
+            .into_iter()
+            .map(|(key, entry)| (key.to_string(), entry.clone()))
+            .collect_vec();
+        assert_eq!(
+            continuations,
+            vec![
+                (S("C-x"), KeymapEntry::Incomplete),
+                (S("H-e"), KeymapEntry::Complete("emacs")),
+                (S("H-t"), KeymapEntry::Complete("alacritty")),
+            ]
+        );
+
+        // Shift mask is ignored as `Keymap::get()` does.
+        let keyseq = vec![Key {
+            modmask: ModMask::MOD5 | ModMask::SHIFT,
+            keysym: Keysym::x,
+        }]
+        .into();
+        assert_eq!(keymap.continuations(&keyseq).len(), 3);
+
+        assert!(keymap.continuations(&kbd("H-t")).is_empty());
+    }
 }
@@ -4,6 +4,7 @@ use eyre::eyre;
 use itertools::Itertools;
 use smithay::input::keyboard::{KeysymHandle, XkbContextHandler};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use xkbcommon::xkb::{self, Keysym};
 
 bitflags::bitflags! {
@@ -21,12 +22,35 @@ bitflags::bitflags! {
     }
 }
 
+/// Prefixes used by `Display` of `Key` and `KeySeq`. `KeySeqSerde::default()` accepts them.
+const CANONICAL_PREFIXES: &[(&str, ModMask)] = &[
+    ("C", ModMask::CONTROL),
+    ("M", ModMask::MOD1),
+    ("Mod2", ModMask::MOD2),
+    ("Mod3", ModMask::MOD3),
+    ("s", ModMask::MOD4),
+    ("H", ModMask::MOD5),
+    ("Lock", ModMask::LOCK),
+    ("S", ModMask::SHIFT),
+];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Key {
     pub modmask: ModMask,
     pub keysym: Keysym,
 }
 
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (prefix, m) in CANONICAL_PREFIXES {
+            if self.modmask.contains(*m) {
+                write!(f, "{}-", prefix)?;
+            }
+        }
+        write!(f, "{}", xkb::keysym_get_name(self.keysym))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct KeySeq(Vec<Key>);
 
@@ -36,6 +60,12 @@ impl From<Vec<Key>> for KeySeq {
     }
 }
 
+impl fmt::Display for KeySeq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.iter().join(" "))
+    }
+}
+
 impl KeySeq {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
@@ -97,6 +127,10 @@ impl KeySeq {
     pub fn pop(&mut self) {
         self.0.pop();
     }
+
+    pub fn eq_without_shift_mask(&self, other: &KeySeq) -> bool {
+        KeySeqWithoutShiftMask::from(self.clone()) == KeySeqWithoutShiftMask::from(other.clone())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -113,10 +147,27 @@ impl From<KeySeq> for KeySeqWithoutShiftMask {
     }
 }
 
+impl KeySeqWithoutShiftMask {
+    pub fn as_keyseq(&self) -> &KeySeq {
+        &self.0
+    }
+}
+
 pub struct KeySeqSerde {
     map: HashMap<String, ModMask>,
 }
 
+/// Accepts the prefixes that `Display` of `KeySeq` emits, i.e. `kbd(&keyseq.to_string())` round-trips.
+impl Default for KeySeqSerde {
+    fn default() -> Self {
+        let map = CANONICAL_PREFIXES
+            .iter()
+            .map(|&(prefix, m)| (prefix.to_string(), m))
+            .collect();
+        Self { map }
+    }
+}
+
 impl KeySeqSerde {
     pub fn new(map: HashMap<String, ModMask>) -> Self {
         Self { map }
@@ -246,4 +297,20 @@ mod tests {
         });
         assert_eq!(keyseq_serde.kbd(s).unwrap().as_keys(), res);
     }
+
+    #[rstest(
+        keys, s,
+        case(&[nomod(Keysym::a)], "a"),
+        case(&[nomod(Keysym::A)], "A"),
+        case(&[control_mod1(Keysym::a)], "C-M-a"),
+        case(&[mod4(Keysym::Return)], "s-Return"),
+        case(&[Key { modmask: ModMask::SHIFT | ModMask::MOD5, keysym: Keysym::dollar }], "H-S-dollar"),
+        case(&[mod5(Keysym::x), mod5(Keysym::t)], "H-x H-t"),
+    )]
+    #[trace]
+    fn test_keyseq_display_round_trip(keys: &[Key], s: &str) {
+        let keyseq = KeySeq::from(keys.to_vec());
+        assert_eq!(keyseq.to_string(), s);
+        assert_eq!(KeySeqSerde::default().kbd(s).unwrap(), keyseq);
+    }
 }
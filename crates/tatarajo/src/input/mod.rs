@@ -1,5 +1,8 @@
+pub(crate) mod capture;
+pub(crate) mod gesture;
 pub(crate) mod keymap;
 mod keyseq;
 
+pub use gesture::{SwipeBinding, SwipeDirection};
 pub use keymap::Keymap;
-pub use keyseq::{KeySeq, KeySeqSerde, ModMask};
+pub use keyseq::{Key, KeySeq, KeySeqSerde, ModMask};
@@ -1,19 +1,38 @@
+use crate::action::{Action, ActionChangeVt, ActionFnI};
+use crate::config::FocusPolicy;
+use crate::focus::KeyboardFocusTarget;
+use crate::input::gesture::SwipeTracker;
 use crate::input::keymap::KeymapEntry;
-use crate::input::KeySeq;
+use crate::input::{Key, KeySeq};
 use crate::state::TatarajoState;
 use crate::util::Id;
 use crate::view::window::Window;
 use smithay::backend::input::{
-    AbsolutePositionEvent, Axis, AxisSource, ButtonState, Event, InputBackend, InputEvent,
-    KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent,
+    AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, Event, GestureBeginEvent,
+    GestureEndEvent, GesturePinchUpdateEvent as _, GestureSwipeUpdateEvent as _, InputBackend,
+    InputEvent, KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent, ProximityState,
+    TabletToolButtonEvent, TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent,
+    TabletToolTipState, TouchEvent as _,
 };
-use smithay::input::keyboard::FilterResult;
-use smithay::input::pointer::{AxisFrame, ButtonEvent, MotionEvent};
-use smithay::utils::{Logical, Point, Serial, SERIAL_COUNTER};
+use smithay::input::keyboard::{FilterResult, Keysym, KeysymHandle};
+use smithay::input::pointer::{
+    AxisFrame, ButtonEvent, GestureHoldBeginEvent, GestureHoldEndEvent, GesturePinchBeginEvent,
+    GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
+    GestureSwipeUpdateEvent, MotionEvent,
+};
+use smithay::input::touch::{DownEvent, MotionEvent as TouchMotionEvent, UpEvent};
+use smithay::utils::{Logical, Point, Serial, Size, SERIAL_COUNTER};
+use smithay::wayland::seat::WaylandFocus;
+use smithay::wayland::tablet_manager::{TabletDescriptor, TabletSeatTrait};
+use std::time::Duration;
 
 impl TatarajoState {
     pub(crate) fn process_input_event<I: InputBackend>(&mut self, event: InputEvent<I>) {
         let serial = SERIAL_COUNTER.next_serial();
+        self.inner
+            .idle_state
+            .notifier_state
+            .notify_activity(&self.inner.seat);
 
         match &event {
             InputEvent::DeviceAdded { .. } | InputEvent::DeviceRemoved { .. } => {
@@ -23,6 +42,8 @@ impl TatarajoState {
             InputEvent::Keyboard { event } => {
                 let time = Event::time_msec(event);
 
+                self.switch_xkb_keymap_for_device(&event.device().name());
+
                 // Note that `Seat::get_keyboard()` locks a field. If we call `TatarajoState::process_action()` in the `filter` (the
                 // last argument), it will deadlock (if it hits a path calling e.g. `Seat::get_keyborad()` in it).
                 let action = self.inner.seat.get_keyboard().unwrap().input(
@@ -36,18 +57,34 @@ impl TatarajoState {
                     time,
                     |this, _, keysym_handle| match event.state() {
                         KeyState::Pressed => {
+                            if this.inner.session_lock_state.is_locked() {
+                                return this.on_key_while_locked(&keysym_handle);
+                            }
+                            if this.inner.key_capture.is_some() {
+                                return this.on_key_captured(&keysym_handle);
+                            }
+
                             let was_empty = this.inner.keyseq.is_empty();
                             for key in KeySeq::extract(&keysym_handle).into_vec() {
+                                if !this.inner.keyseq.is_empty() && this.is_keyseq_cancel(&key) {
+                                    debug!("key sequence cancelled: {}", this.inner.keyseq);
+                                    this.clear_keyseq();
+                                    return FilterResult::Intercept(None);
+                                }
+
                                 this.inner.keyseq.push(key);
                                 debug!("{:?}", this.inner.keyseq);
                                 match this.inner.keymap.get(&this.inner.keyseq).clone() {
                                     KeymapEntry::Complete(action) => {
-                                        this.inner.keyseq.clear();
+                                        this.clear_keyseq();
                                         return FilterResult::Intercept(Some(action));
                                     }
                                     KeymapEntry::Incomplete => {}
                                     KeymapEntry::None => {
-                                        this.inner.keyseq.clear();
+                                        if !was_empty {
+                                            debug!("undefined key sequence: {}", this.inner.keyseq);
+                                        }
+                                        this.clear_keyseq();
                                         if was_empty {
                                             return FilterResult::Forward;
                                         } else {
@@ -56,10 +93,14 @@ impl TatarajoState {
                                     }
                                 }
                             }
+                            this.on_keyseq_updated();
                             FilterResult::Intercept(None)
                         }
                         KeyState::Released => {
-                            if this.inner.keyseq.is_empty() {
+                            if this.inner.session_lock_state.is_locked()
+                                || (this.inner.keyseq.is_empty()
+                                    && this.inner.key_capture.is_none())
+                            {
                                 FilterResult::Forward
                             } else {
                                 FilterResult::Intercept(None)
@@ -115,9 +156,11 @@ impl TatarajoState {
             InputEvent::PointerMotionAbsolute { event } => {
                 let pointer = self.inner.seat.get_pointer().unwrap();
 
-                let output = self.inner.space.outputs().next().unwrap();
-                let output_geo = self.inner.space.output_geometry(output).unwrap();
-                let pos = event.position_transformed(output_geo.size) + output_geo.loc.to_f64();
+                let Some(pos) = self
+                    .absolute_position(&event.device(), |size| event.position_transformed(size))
+                else {
+                    return;
+                };
                 let under = self.surface_under(pos);
 
                 pointer.motion(
@@ -150,15 +193,24 @@ impl TatarajoState {
             }
             InputEvent::PointerAxis { event } => {
                 let source = event.source();
-
+                let factor = self
+                    .inner
+                    .input_device_configs
+                    .get(&event.device().id())
+                    .and_then(|config| config.scroll_factor)
+                    .unwrap_or(1.0);
+
+                // A wheel click is 120 in v120 and we treat it as 3 lines.
                 let horizontal_amount = event.amount(Axis::Horizontal).unwrap_or_else(|| {
                     event.amount_v120(Axis::Horizontal).unwrap_or(0.0) * 3.0 / 120.
-                });
+                }) * factor;
                 let vertical_amount = event.amount(Axis::Vertical).unwrap_or_else(|| {
                     event.amount_v120(Axis::Vertical).unwrap_or(0.0) * 3.0 / 120.
-                });
-                let horizontal_amount_discrete = event.amount_v120(Axis::Horizontal);
-                let vertical_amount_discrete = event.amount_v120(Axis::Vertical);
+                }) * factor;
+                let horizontal_amount_discrete =
+                    event.amount_v120(Axis::Horizontal).map(|x| x * factor);
+                let vertical_amount_discrete =
+                    event.amount_v120(Axis::Vertical).map(|x| x * factor);
 
                 let mut frame = AxisFrame::new(event.time_msec()).source(source);
                 if horizontal_amount != 0.0 {
@@ -187,6 +239,312 @@ impl TatarajoState {
                 pointer.axis(self, frame);
                 pointer.frame(self);
             }
+            InputEvent::GestureSwipeBegin { event } => {
+                let fingers = event.fingers();
+                let is_bound = !self.inner.session_lock_state.is_locked()
+                    && self
+                        .inner
+                        .config
+                        .swipe_bindings
+                        .iter()
+                        .any(|binding| binding.fingers == fingers);
+                if is_bound {
+                    self.inner.swipe_tracker = Some(SwipeTracker::new(fingers));
+                } else {
+                    let pointer = self.inner.seat.get_pointer().unwrap();
+                    pointer.gesture_swipe_begin(
+                        self,
+                        &GestureSwipeBeginEvent {
+                            serial,
+                            time: event.time_msec(),
+                            fingers,
+                        },
+                    );
+                }
+            }
+            InputEvent::GestureSwipeUpdate { event } => {
+                if let Some(tracker) = &mut self.inner.swipe_tracker {
+                    let fingers = tracker.fingers();
+                    let direction =
+                        tracker.update(event.delta(), self.inner.config.swipe_threshold);
+                    let action = direction.and_then(|direction| {
+                        self.inner
+                            .config
+                            .swipe_bindings
+                            .iter()
+                            .find(|binding| {
+                                binding.fingers == fingers && binding.direction == direction
+                            })
+                            .map(|binding| binding.action.clone())
+                    });
+                    if let Some(action) = action {
+                        self.process_action(&action);
+                    }
+                } else {
+                    let pointer = self.inner.seat.get_pointer().unwrap();
+                    pointer.gesture_swipe_update(
+                        self,
+                        &GestureSwipeUpdateEvent {
+                            time: event.time_msec(),
+                            delta: event.delta(),
+                        },
+                    );
+                }
+            }
+            InputEvent::GestureSwipeEnd { event } => {
+                if self.inner.swipe_tracker.take().is_none() {
+                    let pointer = self.inner.seat.get_pointer().unwrap();
+                    pointer.gesture_swipe_end(
+                        self,
+                        &GestureSwipeEndEvent {
+                            serial,
+                            time: event.time_msec(),
+                            cancelled: event.cancelled(),
+                        },
+                    );
+                }
+            }
+            InputEvent::GesturePinchBegin { event } => {
+                let pointer = self.inner.seat.get_pointer().unwrap();
+                pointer.gesture_pinch_begin(
+                    self,
+                    &GesturePinchBeginEvent {
+                        serial,
+                        time: event.time_msec(),
+                        fingers: event.fingers(),
+                    },
+                );
+            }
+            InputEvent::GesturePinchUpdate { event } => {
+                let pointer = self.inner.seat.get_pointer().unwrap();
+                pointer.gesture_pinch_update(
+                    self,
+                    &GesturePinchUpdateEvent {
+                        time: event.time_msec(),
+                        delta: event.delta(),
+                        scale: event.scale(),
+                        rotation: event.rotation(),
+                    },
+                );
+            }
+            InputEvent::GesturePinchEnd { event } => {
+                let pointer = self.inner.seat.get_pointer().unwrap();
+                pointer.gesture_pinch_end(
+                    self,
+                    &GesturePinchEndEvent {
+                        serial,
+                        time: event.time_msec(),
+                        cancelled: event.cancelled(),
+                    },
+                );
+            }
+            InputEvent::GestureHoldBegin { event } => {
+                let pointer = self.inner.seat.get_pointer().unwrap();
+                pointer.gesture_hold_begin(
+                    self,
+                    &GestureHoldBeginEvent {
+                        serial,
+                        time: event.time_msec(),
+                        fingers: event.fingers(),
+                    },
+                );
+            }
+            InputEvent::GestureHoldEnd { event } => {
+                let pointer = self.inner.seat.get_pointer().unwrap();
+                pointer.gesture_hold_end(
+                    self,
+                    &GestureHoldEndEvent {
+                        serial,
+                        time: event.time_msec(),
+                        cancelled: event.cancelled(),
+                    },
+                );
+            }
+            InputEvent::TouchDown { event } => {
+                let Some(touch) = self.inner.seat.get_touch() else {
+                    return;
+                };
+                let Some(pos) = self
+                    .absolute_position(&event.device(), |size| event.position_transformed(size))
+                else {
+                    return;
+                };
+
+                if !touch.is_grabbed() {
+                    self.update_focus(serial, pos);
+                }
+
+                let under = self.surface_under(pos);
+                touch.down(
+                    self,
+                    under,
+                    &DownEvent {
+                        slot: event.slot(),
+                        location: pos,
+                        serial,
+                        time: event.time_msec(),
+                    },
+                );
+            }
+            InputEvent::TouchMotion { event } => {
+                let Some(touch) = self.inner.seat.get_touch() else {
+                    return;
+                };
+                let Some(pos) = self
+                    .absolute_position(&event.device(), |size| event.position_transformed(size))
+                else {
+                    return;
+                };
+
+                let under = self.surface_under(pos);
+                touch.motion(
+                    self,
+                    under,
+                    &TouchMotionEvent {
+                        slot: event.slot(),
+                        location: pos,
+                        time: event.time_msec(),
+                    },
+                );
+            }
+            InputEvent::TouchUp { event } => {
+                let Some(touch) = self.inner.seat.get_touch() else {
+                    return;
+                };
+
+                touch.up(
+                    self,
+                    &UpEvent {
+                        slot: event.slot(),
+                        serial,
+                        time: event.time_msec(),
+                    },
+                );
+            }
+            InputEvent::TouchFrame { .. } => {
+                let Some(touch) = self.inner.seat.get_touch() else {
+                    return;
+                };
+
+                touch.frame(self);
+            }
+            InputEvent::TouchCancel { .. } => {
+                let Some(touch) = self.inner.seat.get_touch() else {
+                    return;
+                };
+
+                touch.cancel(self);
+            }
+            InputEvent::TabletToolProximity { event } => {
+                let Some(pos) = self
+                    .absolute_position(&event.device(), |size| event.position_transformed(size))
+                else {
+                    return;
+                };
+
+                let tool_desc = event.tool();
+                let tablet_seat = self.inner.seat.tablet_seat();
+                tablet_seat.add_tool::<Self>(&self.inner.display_handle, &tool_desc);
+                let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&event.device()));
+                let tool = tablet_seat.get_tool(&tool_desc);
+
+                match event.state() {
+                    ProximityState::In => {
+                        self.inner
+                            .tablet_tool_cursors
+                            .set_location(&tool_desc, Some(pos));
+
+                        let under = self
+                            .surface_under(pos)
+                            .and_then(|(f, loc)| f.wl_surface().map(|s| (s, loc)));
+                        if let (Some(under), Some(tablet), Some(tool)) = (under, tablet, tool) {
+                            tool.proximity_in(pos, under, &tablet, serial, event.time_msec());
+                        }
+                    }
+                    ProximityState::Out => {
+                        self.inner
+                            .tablet_tool_cursors
+                            .set_location(&tool_desc, None);
+
+                        if let Some(tool) = tool {
+                            tool.proximity_out(event.time_msec());
+                        }
+                    }
+                }
+            }
+            InputEvent::TabletToolAxis { event } => {
+                let Some(pos) = self
+                    .absolute_position(&event.device(), |size| event.position_transformed(size))
+                else {
+                    return;
+                };
+
+                let tool_desc = event.tool();
+                self.inner
+                    .tablet_tool_cursors
+                    .set_location(&tool_desc, Some(pos));
+
+                let tablet_seat = self.inner.seat.tablet_seat();
+                let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&event.device()));
+                let tool = tablet_seat.get_tool(&tool_desc);
+                let (Some(tablet), Some(tool)) = (tablet, tool) else {
+                    return;
+                };
+
+                if event.pressure_has_changed() {
+                    tool.pressure(event.pressure());
+                }
+                if event.distance_has_changed() {
+                    tool.distance(event.distance());
+                }
+                if event.tilt_has_changed() {
+                    tool.tilt(event.tilt());
+                }
+                if event.slider_has_changed() {
+                    tool.slider_position(event.slider_position());
+                }
+                if event.rotation_has_changed() {
+                    tool.rotation(event.rotation());
+                }
+                if event.wheel_has_changed() {
+                    tool.wheel(event.wheel_delta(), event.wheel_delta_discrete());
+                }
+
+                let under = self
+                    .surface_under(pos)
+                    .and_then(|(f, loc)| f.wl_surface().map(|s| (s, loc)));
+                tool.motion(pos, under, &tablet, serial, event.time_msec());
+            }
+            InputEvent::TabletToolTip { event } => {
+                let tool_desc = event.tool();
+                let Some(tool) = self.inner.seat.tablet_seat().get_tool(&tool_desc) else {
+                    return;
+                };
+
+                match event.tip_state() {
+                    TabletToolTipState::Down => {
+                        if let Some(pos) = self.inner.tablet_tool_cursors.location(&tool_desc) {
+                            self.update_focus(serial, pos);
+                        }
+                        tool.tip_down(serial, event.time_msec());
+                    }
+                    TabletToolTipState::Up => {
+                        tool.tip_up(event.time_msec());
+                    }
+                }
+            }
+            InputEvent::TabletToolButton { event } => {
+                let Some(tool) = self.inner.seat.tablet_seat().get_tool(&event.tool()) else {
+                    return;
+                };
+
+                tool.button(
+                    event.button(),
+                    event.button_state(),
+                    serial,
+                    event.time_msec(),
+                );
+            }
             _ => {}
         }
 
@@ -201,9 +559,153 @@ impl TatarajoState {
         }
     }
 
-    #[allow(unused_variables)]
+    /// Maps an absolute position of a touchscreen or a tablet to the output given by `map_to_output` of
+    /// `TATARAJO_LIBINPUT_CONFIG`, or the first output.
+    ///
+    /// Positions are relative to the panel in its native orientation, so the output transform is applied.
+    fn absolute_position<D, F>(
+        &self,
+        device: &D,
+        position_transformed: F,
+    ) -> Option<Point<f64, Logical>>
+    where
+        D: Device,
+        F: FnOnce(Size<i32, Logical>) -> Point<f64, Logical>,
+    {
+        let space = &self.inner.space;
+        let output_name = self
+            .inner
+            .input_device_configs
+            .get(&device.id())
+            .and_then(|config| config.map_to_output.as_deref());
+        let output = output_name
+            .and_then(|name| space.outputs().find(|output| output.name() == name))
+            .or_else(|| space.outputs().next())?;
+        let output_geo = space.output_geometry(output)?;
+
+        let transform = output.current_transform();
+        let size = transform.invert().transform_size(output_geo.size);
+        let pos = position_transformed(size);
+        let pos = transform.transform_point_in(pos, &size.to_f64());
+        Some(pos + output_geo.loc.to_f64())
+    }
+
+    /// Sets the keymap for the device if it differs from the current one.
+    fn switch_xkb_keymap_for_device(&mut self, device_name: &str) {
+        let keymap_config = self.inner.xkb_config.keymap_for_device(device_name);
+        if keymap_config == self.inner.xkb_keymap_config {
+            return;
+        }
+
+        debug!(
+            "switching keymap for device: device_name = {}, keymap_config = {:?}",
+            device_name, keymap_config
+        );
+        let keyboard = self.inner.seat.get_keyboard().unwrap();
+        // `set_xkb_config()` resets the layout chosen by `ActionSwitchKeyboardLayout`. Save and restore it.
+        let layout = keyboard.with_xkb_state(self, |context| {
+            context.xkb().lock().unwrap().active_layout()
+        });
+        self.inner
+            .xkb_layouts
+            .insert(self.inner.xkb_keymap_config.clone(), layout);
+        match keyboard.set_xkb_config(self, keymap_config.as_smithay()) {
+            Ok(()) => {
+                if let Some(layout) = self.inner.xkb_layouts.get(&keymap_config).copied() {
+                    keyboard.with_xkb_state(self, |mut context| context.set_layout(layout));
+                }
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to set keymap for device: device_name = {}, error = {:?}",
+                    device_name, err
+                );
+            }
+        }
+        // Record it even if it failed so that we don't retry it per key event.
+        self.inner.xkb_keymap_config = keymap_config;
+    }
+
+    pub(crate) fn is_keyseq_cancel(&self, key: &Key) -> bool {
+        let Some(cancel) = &self.inner.config.keyseq_cancel else {
+            return false;
+        };
+
+        cancel.eq_without_shift_mask(&vec![key.clone()].into())
+    }
+
+    /// Forwards keys to the lock surface, except for VT switching.
+    fn on_key_while_locked(
+        &mut self,
+        keysym_handle: &KeysymHandle<'_>,
+    ) -> FilterResult<Option<Action>> {
+        let keysym = keysym_handle.modified_sym().raw();
+        let vts = Keysym::XF86_Switch_VT_1.raw()..=Keysym::XF86_Switch_VT_12.raw();
+        if vts.contains(&keysym) {
+            let vt = (keysym - Keysym::XF86_Switch_VT_1.raw() + 1) as i32;
+            return FilterResult::Intercept(Some(ActionChangeVt(vt).into_action()));
+        }
+        FilterResult::Forward
+    }
+
+    pub(crate) fn clear_keyseq(&mut self) {
+        self.inner.keyseq.clear();
+        self.on_keyseq_updated();
+    }
+
+    /// Restarts the timeout of the pending key sequence and updates the overlay.
+    fn on_keyseq_updated(&mut self) {
+        use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+
+        if let Some(token) = self.inner.keyseq_timer.take() {
+            self.inner.loop_handle.remove(token);
+        }
+
+        if self.inner.keyseq.is_empty() {
+            self.inner.keyseq_overlay.clear();
+            return;
+        }
+
+        if let Some(timeout) = self.inner.config.keyseq_timeout {
+            let token = self
+                .inner
+                .loop_handle
+                .insert_source(Timer::from_duration(timeout), |_, _, state| {
+                    debug!("key sequence timed out: {}", state.inner.keyseq);
+                    state.inner.keyseq_timer = None;
+                    state.clear_keyseq();
+                    TimeoutAction::Drop
+                })
+                .unwrap(/* safety: Registration of `Timer` never fails. */);
+            self.inner.keyseq_timer = Some(token);
+        }
+
+        if self.inner.config.keyseq_overlay {
+            let mut lines = vec![format!("{} -", self.inner.keyseq)];
+            for (key, entry) in self.inner.keymap.continuations(&self.inner.keyseq) {
+                let description = match entry {
+                    KeymapEntry::Complete(action) => action.to_string(),
+                    KeymapEntry::Incomplete => "+prefix".to_string(),
+                    KeymapEntry::None => unreachable!(),
+                };
+                let key = key.to_string();
+                lines.push(format!("  {:<12} {}", key, description));
+            }
+            self.inner.keyseq_overlay.set_lines(lines);
+        }
+    }
+
     fn update_focus(&mut self, serial: Serial, pos: Point<f64, Logical>) {
+        if self.inner.session_lock_state.is_locked() {
+            self.focus_lock_surface(Some(serial));
+            return;
+        }
+
         let Some(window) = self.inner.space.element_under(pos).map(|(w, _)| w).cloned() else {
+            if self.inner.config.focus_policy == FocusPolicy::Strict {
+                self.inner.view.clear_focus();
+                self.reflect_focus_from_stackset(Some(serial));
+            }
             return;
         };
 
@@ -211,8 +713,55 @@ impl TatarajoState {
         self.reflect_focus_from_stackset(Some(serial));
     }
 
+    /// Moves the pointer to the center of the focused window unless it is already on the window.
+    pub(crate) fn warp_pointer_to_focused_window(&mut self) {
+        let Some(window) = self.inner.view.focused_window() else {
+            return;
+        };
+        let Some(geometry) = self.inner.space.element_geometry(window) else {
+            return;
+        };
+        let pointer = self.inner.seat.get_pointer().unwrap();
+        if geometry.to_f64().contains(pointer.current_location()) {
+            return;
+        }
+
+        let center = geometry.loc.to_f64()
+            + Point::from((geometry.size.w as f64 / 2.0, geometry.size.h as f64 / 2.0));
+        let under = self.surface_under(center);
+        let time = Duration::from(self.inner.clock.now()).as_millis() as u32;
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                serial: SERIAL_COUNTER.next_serial(),
+                time,
+                location: center,
+            },
+        );
+        pointer.frame(self);
+    }
+
     pub(crate) fn reflect_focus_from_stackset(&mut self, serial: Option<Serial>) {
+        self.inner.view.update_focus_history();
+        self.update_status_log();
+        self.inner.refresh_ext_workspace();
+        self.inner.refresh_foreign_toplevel();
+        self.inner.refresh_idle_inhibit();
+
+        if self.inner.session_lock_state.is_locked() {
+            self.focus_lock_surface(serial);
+            return;
+        }
+
         let Some(window) = self.inner.view.focused_window() else {
+            // Unfocus a window that is hidden or unfocused by `View::clear_focus()`. Other targets, e.g. layer surfaces,
+            // keep focus.
+            let keyboard = self.inner.seat.get_keyboard().unwrap();
+            if let Some(KeyboardFocusTarget::Window(_)) = keyboard.current_focus() {
+                let serial = serial.unwrap_or_else(|| SERIAL_COUNTER.next_serial());
+                keyboard.set_focus(self, None, serial);
+            }
             return;
         };
 
@@ -232,22 +781,22 @@ impl TatarajoState {
     }
 }
 
-// Focus follows mouse.
-//
-// Prevents updating focus due to too high sensitivity of touchpad.
+// Decides whether focus should be updated by pointer events, following `FocusPolicy`.
 //
-// TODO: Stabilize interface and make it public for configuration.
+// For `FocusPolicy::Sloppy`, prevents updating focus due to too high sensitivity of touchpad.
 pub(crate) struct FocusUpdateDecider {
+    policy: FocusPolicy,
+    distance_threshold: f64,
     last_window_id: Option<Id<Window>>,
     last_pos: Point<f64, Logical>,
 }
 
 #[allow(dead_code)]
 impl FocusUpdateDecider {
-    const DISTANCE_THRESHOLD: f64 = 16.0;
-
-    pub fn new() -> Self {
+    pub fn new(policy: FocusPolicy, distance_threshold: f64) -> Self {
         Self {
+            policy,
+            distance_threshold,
             last_window_id: None,
             last_pos: Point::default(),
         }
@@ -267,7 +816,27 @@ impl FocusUpdateDecider {
         }
 
         match event {
-            InputEvent::PointerMotion { .. } | InputEvent::PointerMotionAbsolute { .. } => {
+            InputEvent::PointerMotion { .. } | InputEvent::PointerMotionAbsolute { .. }
+                if self.policy == FocusPolicy::Strict =>
+            {
+                let pointer = seat.get_pointer().unwrap();
+                if pointer.is_grabbed() {
+                    return false;
+                }
+
+                // Update only if the window under the pointer is not focused, so that focus is not updated per motion.
+                let under = space
+                    .element_under(pointer.current_location())
+                    .map(|(w, _)| w.smithay_window().clone());
+                let focus = match seat.get_keyboard().unwrap().current_focus() {
+                    Some(KeyboardFocusTarget::Window(w)) => Some(w),
+                    _ => None,
+                };
+                under != focus
+            }
+            InputEvent::PointerMotion { .. } | InputEvent::PointerMotionAbsolute { .. }
+                if self.policy == FocusPolicy::Sloppy =>
+            {
                 // Requirements:
                 //
                 // - Focus should be updated when mouse enters to another window.
@@ -281,7 +850,7 @@ impl FocusUpdateDecider {
                 let distance = (d.x * d.x + d.y * d.y).sqrt();
 
                 let ret =
-                    self.last_window_id != under_window_id || distance > Self::DISTANCE_THRESHOLD;
+                    self.last_window_id != under_window_id || distance > self.distance_threshold;
                 if ret {
                     self.last_window_id = under_window_id;
                     self.last_pos = center_of_pixel(pos);
@@ -9,6 +9,13 @@ impl TatarajoState {
         &self,
         pos: Point<f64, Logical>,
     ) -> Option<(PointerFocusTarget, Point<i32, Logical>)> {
+        if self.inner.session_lock_state.is_locked() {
+            return self
+                .inner
+                .session_lock_state
+                .surface_under(&self.inner.space, pos);
+        }
+
         let output = self.inner.space.outputs().find(|o| {
             let geometry = self.inner.space.output_geometry(o).unwrap();
             geometry.contains(pos.to_i32_round())
@@ -12,23 +12,36 @@ extern crate maplit;
 
 pub mod action;
 pub mod backend;
+pub mod config;
 pub mod cursor;
 mod envvar;
+pub(crate) mod ext_workspace;
 mod external_trait_def;
 pub mod focus;
+pub(crate) mod foreign_toplevel;
+pub(crate) mod idle;
 pub mod input;
 pub(crate) mod input_event;
 pub mod input_handler;
 pub(crate) mod model;
+pub(crate) mod output_power;
+pub(crate) mod overlay;
 pub mod pointer;
+pub mod process;
+pub mod prompt;
 pub mod render;
 pub(crate) mod render_loop;
+pub(crate) mod screencopy;
+pub(crate) mod session_lock;
 pub mod shell;
 pub mod state;
 pub mod state_delegate;
+pub mod status_log;
+pub(crate) mod tablet;
 #[allow(unused)]
 pub(crate) mod util;
 pub mod view;
+pub(crate) mod window_picker;
 pub(crate) mod wl_global;
 
 pub use state::{ClientState, TatarajoState};
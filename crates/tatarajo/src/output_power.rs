@@ -0,0 +1,181 @@
+//! Output power management, i.e. DPMS, used by `ActionDpms` and `wlr-output-power-management-unstable-v1`.
+//!
+//! Backends turn outputs off and stop rendering them in `BackendI::set_output_power()`.
+
+use crate::backend::BackendI;
+use crate::state::{ClientState, TatarajoState};
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_manager_v1::{
+    self, ZwlrOutputPowerManagerV1,
+};
+use smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_v1::{
+    self, Mode, ZwlrOutputPowerV1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+const VERSION: u32 = 1;
+
+pub(crate) struct OutputPowerState {
+    /// Names of outputs that are turned off.
+    off_outputs: Vec<String>,
+    powers: Vec<(Output, ZwlrOutputPowerV1)>,
+}
+
+impl OutputPowerState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        display_handle.create_global::<TatarajoState, ZwlrOutputPowerManagerV1, _>(VERSION, ());
+        Self {
+            off_outputs: vec![],
+            powers: vec![],
+        }
+    }
+
+    pub fn is_on(&self, output: &Output) -> bool {
+        !self.off_outputs.contains(&output.name())
+    }
+
+    /// Records that the output is off after it is set up again, e.g. by `ActionReloadOutputConfig`. The backend must
+    /// have turned it off.
+    pub fn keep_off(&mut self, output: &Output) {
+        if self.is_on(output) {
+            self.off_outputs.push(output.name());
+        }
+    }
+
+    /// Sends `failed` to power objects of the removed output. The output will be on when it is connected again.
+    pub fn remove_output(&mut self, output: &Output) {
+        self.off_outputs.retain(|name| *name != output.name());
+        self.powers.retain(|(o, power)| {
+            if o == output {
+                power.failed();
+            }
+            o != output
+        });
+    }
+}
+
+impl TatarajoState {
+    /// Turns the output on or off, and notifies clients.
+    pub(crate) fn set_output_power(&mut self, output: &Output, on: bool) {
+        let power_state = &mut self.inner.output_power_state;
+        if power_state.is_on(output) == on {
+            return;
+        }
+
+        info!(
+            "turning output {} {}",
+            output.name(),
+            if on { "on" } else { "off" }
+        );
+        if on {
+            power_state
+                .off_outputs
+                .retain(|name| *name != output.name());
+        } else {
+            power_state.off_outputs.push(output.name());
+            // The output isn't rendered, so captures of it never finish.
+            self.inner.screencopy_state.fail_pending(output);
+        }
+        self.backend.set_output_power(output, on);
+
+        let mode = if on { Mode::On } else { Mode::Off };
+        for (o, power) in &self.inner.output_power_state.powers {
+            if o == output {
+                power.mode(mode);
+            }
+        }
+    }
+}
+
+impl GlobalDispatch<ZwlrOutputPowerManagerV1, ()> for TatarajoState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrOutputPowerManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        client
+            .get_data::<ClientState>()
+            .map_or(true, |client_state| client_state.security_context.is_none())
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerManagerV1, ()> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ZwlrOutputPowerManagerV1,
+        request: zwlr_output_power_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwlr_output_power_manager_v1::Request::GetOutputPower { id, output } = request {
+            let power = data_init.init(id, ());
+            let Some(output) = Output::from_resource(&output) else {
+                power.failed();
+                return;
+            };
+
+            let power_state = &mut state.inner.output_power_state;
+            power.mode(if power_state.is_on(&output) {
+                Mode::On
+            } else {
+                Mode::Off
+            });
+            power_state.powers.push((output, power));
+        }
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerV1, ()> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrOutputPowerV1,
+        request: zwlr_output_power_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwlr_output_power_v1::Request::SetMode { mode } = request {
+            let on = match mode {
+                WEnum::Value(Mode::On) => true,
+                WEnum::Value(Mode::Off) => false,
+                _ => {
+                    resource.post_error(zwlr_output_power_v1::Error::InvalidMode, "invalid mode");
+                    return;
+                }
+            };
+            let output = state
+                .inner
+                .output_power_state
+                .powers
+                .iter()
+                .find(|(_, power)| power == resource)
+                .map(|(output, _)| output.clone());
+            match output {
+                Some(output) => state.set_output_power(&output, on),
+                // The output is removed.
+                None => resource.failed(),
+            }
+        }
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &ZwlrOutputPowerV1, _data: &()) {
+        state
+            .inner
+            .output_power_state
+            .powers
+            .retain(|(_, power)| power != resource);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::render::CustomRenderElement;
+use crate::state::{InnerState, TatarajoState};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::memory::{
+    MemoryRenderBuffer, MemoryRenderBufferRenderElement,
+};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::{ImportAll, ImportMem, Renderer, Texture};
+use smithay::output::Output;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::utils::{Logical, Point, Scale, Size, Transform};
+use std::time::Duration;
+
+// Glyphs of printable ASCII characters (0x20 -- 0x7e). Each glyph is 8x16 pixels of 8-bit coverage.
+// Rasterized from DejaVu Sans Mono.
+static FONT: &[u8] = include_bytes!("../resources/font_8x16.bin");
+const GLYPH_WIDTH: usize = 8;
+const GLYPH_HEIGHT: usize = 16;
+const PADDING: usize = 8;
+const MARGIN: i32 = 32;
+// Premultiplied BGRA, i.e. `Fourcc::Argb8888` in little endian.
+const BACKGROUND: [u8; 4] = [0x1a, 0x1a, 0x1a, 0xe6];
+const FOREGROUND: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+/// Where an overlay is placed in an output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OverlayAnchor {
+    #[default]
+    Bottom,
+    Top,
+    Center,
+}
+
+/// Lines of text drawn by tatarajo itself on top of windows, e.g. a pending key sequence.
+#[derive(Default)]
+pub(crate) struct Overlay {
+    anchor: OverlayAnchor,
+    lines: Vec<String>,
+    buffer: Option<MemoryRenderBuffer>,
+}
+
+impl Overlay {
+    pub fn new(anchor: OverlayAnchor) -> Self {
+        Self {
+            anchor,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        if self.lines == lines {
+            return;
+        }
+
+        self.buffer = (!lines.is_empty()).then(|| render_text(&lines));
+        self.lines = lines;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_lines(vec![]);
+    }
+
+    /// Returns elements placed at the horizontal center of an output of the given size.
+    pub fn render_elements<R>(
+        &self,
+        renderer: &mut R,
+        output_size: Size<i32, Logical>,
+        scale: Scale<f64>,
+    ) -> Vec<CustomRenderElement<R>>
+    where
+        R: Renderer + ImportAll + ImportMem,
+        R::TextureId: Texture + 'static,
+    {
+        let Some(buffer) = &self.buffer else {
+            return vec![];
+        };
+
+        let size = text_size(&self.lines);
+        let y = match self.anchor {
+            OverlayAnchor::Bottom => output_size.h - size.h - MARGIN,
+            OverlayAnchor::Top => MARGIN,
+            OverlayAnchor::Center => (output_size.h - size.h) / 2,
+        };
+        let loc = Point::<i32, Logical>::from(((output_size.w - size.w) / 2, y));
+        match MemoryRenderBufferRenderElement::from_buffer(
+            renderer,
+            loc.to_f64().to_physical(scale),
+            buffer,
+            None,
+            None,
+            None,
+            Kind::Unspecified,
+        ) {
+            Ok(element) => vec![CustomRenderElement::from(element)],
+            Err(err) => {
+                warn!("Failed to import overlay buffer: {:?}", err);
+                vec![]
+            }
+        }
+    }
+}
+
+impl InnerState {
+    /// Returns elements of overlays for `output`.
+    ///
+    /// Overlays are shown only on the output the pointer is on.
+    pub(crate) fn overlay_elements<R>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<CustomRenderElement<R>>
+    where
+        R: Renderer + ImportAll + ImportMem,
+        R::TextureId: Texture + 'static,
+    {
+        let active_output = self
+            .space
+            .output_under(self.pointer.current_location())
+            .next()
+            .or_else(|| self.space.outputs().next());
+        if active_output != Some(output) {
+            return vec![];
+        }
+        let Some(output_geometry) = self.space.output_geometry(output) else {
+            return vec![];
+        };
+        let scale = Scale::from(output.current_scale().fractional_scale());
+
+        let mut elements =
+            self.notification_overlay
+                .render_elements(renderer, output_geometry.size, scale);
+        elements.extend(self.key_capture_overlay.render_elements(
+            renderer,
+            output_geometry.size,
+            scale,
+        ));
+        elements.extend(
+            self.keyseq_overlay
+                .render_elements(renderer, output_geometry.size, scale),
+        );
+        elements
+    }
+}
+
+impl TatarajoState {
+    /// Shows `message` in the notification overlay for `timeout`. It replaces the previous notification.
+    pub(crate) fn notify(&mut self, message: String, timeout: Duration) {
+        if let Some(token) = self.inner.notification_timer.take() {
+            self.inner.loop_handle.remove(token);
+        }
+
+        self.inner
+            .notification_overlay
+            .set_lines(message.lines().map(|l| l.to_string()).collect());
+
+        let token = self
+            .inner
+            .loop_handle
+            .insert_source(Timer::from_duration(timeout), |_, _, state| {
+                state.inner.notification_timer = None;
+                state.inner.notification_overlay.clear();
+                TimeoutAction::Drop
+            })
+            .unwrap(/* safety: Registration of `Timer` never fails. */);
+        self.inner.notification_timer = Some(token);
+    }
+}
+
+fn text_size(lines: &[String]) -> Size<i32, Logical> {
+    let cols = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let w = cols * GLYPH_WIDTH + 2 * PADDING;
+    let h = lines.len() * GLYPH_HEIGHT + 2 * PADDING;
+    (w as i32, h as i32).into()
+}
+
+fn glyph(c: char) -> &'static [u8] {
+    let c = if (' '..='~').contains(&c) { c } else { '?' };
+    let i = (c as usize - 0x20) * GLYPH_WIDTH * GLYPH_HEIGHT;
+    &FONT[i..i + GLYPH_WIDTH * GLYPH_HEIGHT]
+}
+
+fn render_text(lines: &[String]) -> MemoryRenderBuffer {
+    let size = text_size(lines);
+    let w = size.w as usize;
+    let mut data = BACKGROUND.repeat(w * size.h as usize);
+
+    for (row, line) in lines.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            let glyph = glyph(c);
+            let x0 = PADDING + col * GLYPH_WIDTH;
+            let y0 = PADDING + row * GLYPH_HEIGHT;
+            for gy in 0..GLYPH_HEIGHT {
+                for gx in 0..GLYPH_WIDTH {
+                    let coverage = glyph[gy * GLYPH_WIDTH + gx] as u32;
+                    if coverage == 0 {
+                        continue;
+                    }
+                    let i = ((y0 + gy) * w + x0 + gx) * 4;
+                    for (j, (&fg, &bg)) in FOREGROUND.iter().zip(BACKGROUND.iter()).enumerate() {
+                        data[i + j] =
+                            ((fg as u32 * coverage + bg as u32 * (255 - coverage)) / 255) as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    MemoryRenderBuffer::from_slice(
+        &data,
+        Fourcc::Argb8888,
+        (size.w, size.h),
+        1,
+        Transform::Normal,
+        None,
+    )
+}
@@ -0,0 +1,222 @@
+use crate::state::TatarajoState;
+use crate::view::stackset::WorkspaceTag;
+use smithay::wayland::xdg_activation::XdgActivationToken;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Instant;
+
+/// How to spawn a program with `Action::Spawn`.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    /// Command line run by `/bin/sh -c`.
+    pub command: String,
+    /// Environment variables added to the ones of tatarajo.
+    pub envs: Vec<(String, String)>,
+    /// Working directory. Defaults to the one of tatarajo.
+    pub current_dir: Option<PathBuf>,
+    /// Workspace where windows of the process are placed. Defaults to the one focused when it is spawned.
+    pub workspace: Option<WorkspaceTag>,
+}
+
+impl SpawnOptions {
+    pub fn new(command: impl ToString) -> Self {
+        Self {
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A child process spawned by tatarajo.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub command: String,
+    pub workspace: Option<WorkspaceTag>,
+    /// Token exported as `XDG_ACTIVATION_TOKEN` and `DESKTOP_STARTUP_ID`.
+    pub activation_token: Option<String>,
+    pub spawned_at: Instant,
+}
+
+/// Spawns programs and reaps them when they exit, so that they don't remain as zombies.
+pub(crate) struct ProcessManager {
+    log_dir: Option<PathBuf>,
+    children: HashMap<u32, (ProcessInfo, Child)>,
+}
+
+impl ProcessManager {
+    pub fn new(log_dir: Option<PathBuf>) -> Self {
+        Self {
+            log_dir,
+            children: HashMap::new(),
+        }
+    }
+
+    pub fn spawn(
+        &mut self,
+        options: &SpawnOptions,
+        activation_token: Option<String>,
+    ) -> std::io::Result<u32> {
+        let mut command = Command::new("/bin/sh");
+        command
+            .arg("-c")
+            .arg(&options.command)
+            .envs(options.envs.iter().map(|(k, v)| (k, v)));
+        if let Some(token) = &activation_token {
+            command
+                .env("XDG_ACTIVATION_TOKEN", token)
+                .env("DESKTOP_STARTUP_ID", token);
+        }
+        if let Some(dir) = &options.current_dir {
+            command.current_dir(dir);
+        }
+        if let Some(log_dir) = &self.log_dir {
+            let file = open_log_file(log_dir, &options.command)?;
+            command
+                .stdout(Stdio::from(file.try_clone()?))
+                .stderr(Stdio::from(file));
+        }
+
+        let child = command.spawn()?;
+        let pid = child.id();
+        info!("spawned: pid = {}, command = {}", pid, options.command);
+
+        let info = ProcessInfo {
+            pid,
+            command: options.command.clone(),
+            workspace: options.workspace.clone(),
+            activation_token,
+            spawned_at: Instant::now(),
+        };
+        self.children.insert(pid, (info, child));
+
+        Ok(pid)
+    }
+
+    /// Reaps exited children and returns them. It is called on `SIGCHLD`.
+    ///
+    /// Only children in the table are waited, because other ones, e.g. Xwayland, are waited by their owners.
+    pub fn reap(&mut self) -> Vec<ProcessInfo> {
+        let mut exited = vec![];
+        self.children
+            .retain(|&pid, (info, child)| match child.try_wait() {
+                Ok(None) => true,
+                Ok(Some(status)) => {
+                    info!(
+                        "exited: pid = {}, command = {}, status = {}",
+                        pid, info.command, status
+                    );
+                    exited.push(info.clone());
+                    false
+                }
+                Err(err) => {
+                    warn!("failed to wait: pid = {}, error = {}", pid, err);
+                    true
+                }
+            });
+        exited
+    }
+
+    /// Returns running children.
+    pub fn processes(&self) -> impl Iterator<Item = &ProcessInfo> {
+        self.children.values().map(|(info, _)| info)
+    }
+
+    pub fn find_by_activation_token(&self, token: &str) -> Option<&ProcessInfo> {
+        self.processes()
+            .find(|info| info.activation_token.as_deref() == Some(token))
+    }
+
+    /// Returns the child that is `pid` or its nearest ancestor.
+    ///
+    /// `/bin/sh -c` may fork the actual program, and the program may fork again before it creates windows.
+    pub fn find_ancestor(&self, pid: u32) -> Option<&ProcessInfo> {
+        let mut pid = pid;
+        loop {
+            if let Some((info, _)) = self.children.get(&pid) {
+                return Some(info);
+            }
+            pid = parent_pid(pid)?;
+        }
+    }
+}
+
+impl TatarajoState {
+    /// Spawns a program with an xdg-activation token, remembering the workspace its windows are placed on.
+    pub(crate) fn spawn(&mut self, options: &SpawnOptions) -> std::io::Result<u32> {
+        let mut options = options.clone();
+        options
+            .workspace
+            .get_or_insert_with(|| self.inner.view.stackset().workspaces.focus().tag.clone());
+        let (token, _) = self.inner.xdg_activation_state.create_external_token(None);
+        let token = token.to_string();
+
+        self.inner.process_manager.spawn(&options, Some(token))
+    }
+
+    /// Reaps exited children and removes their activation tokens, which are no longer used.
+    pub(crate) fn reap_children(&mut self) {
+        for info in self.inner.process_manager.reap() {
+            if let Some(token) = info.activation_token {
+                self.inner
+                    .xdg_activation_state
+                    .remove_token(&XdgActivationToken::from(token));
+            }
+        }
+    }
+
+    /// Returns the workspace a new window of the process `pid` should be placed on.
+    ///
+    /// Only windows mapped within `Config::spawn_workspace_timeout` after spawn are placed, so that e.g. a window
+    /// opened from a long-running terminal goes to the focused workspace.
+    pub(crate) fn spawned_workspace_for_pid(&self, pid: u32) -> Option<WorkspaceTag> {
+        let info = self.inner.process_manager.find_ancestor(pid)?;
+        self.spawned_workspace(info)
+    }
+
+    /// Same as `spawned_workspace_for_pid()`, for a window activated with the token of the process.
+    pub(crate) fn spawned_workspace_for_activation_token(
+        &self,
+        token: &str,
+    ) -> Option<WorkspaceTag> {
+        let info = self.inner.process_manager.find_by_activation_token(token)?;
+        self.spawned_workspace(info)
+    }
+
+    fn spawned_workspace(&self, info: &ProcessInfo) -> Option<WorkspaceTag> {
+        (info.spawned_at.elapsed() < self.inner.config.spawn_workspace_timeout)
+            .then(|| info.workspace.clone())
+            .flatten()
+    }
+
+    /// Returns running children spawned by `Action::Spawn`.
+    pub fn spawned_processes(&self) -> impl Iterator<Item = &ProcessInfo> {
+        self.inner.process_manager.processes()
+    }
+}
+
+fn open_log_file(log_dir: &Path, command: &str) -> std::io::Result<File> {
+    std::fs::create_dir_all(log_dir)?;
+    let program = command
+        .split_whitespace()
+        .next()
+        .and_then(|s| Path::new(s).file_name())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string());
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_dir.join(format!("{}.log", program)))
+}
+
+fn parent_pid(pid: u32) -> Option<u32> {
+    if pid <= 1 {
+        return None;
+    }
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The second field is the command name in parentheses, which may contain spaces.
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
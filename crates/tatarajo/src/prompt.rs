@@ -0,0 +1,353 @@
+use crate::action::{ActionError, ActionFnI};
+use crate::input::capture::{KeyCapture, KeyCaptureResult};
+use crate::input::{Key, ModMask};
+use crate::state::TatarajoState;
+use std::sync::Arc;
+use xkbcommon::xkb::{self, Keysym};
+
+const MAX_CANDIDATES: usize = 10;
+
+type SubmitFn = dyn Fn(&mut TatarajoState, String) -> Result<(), ActionError>;
+type CompletionFn = dyn Fn(&str) -> Vec<String>;
+
+/// A text prompt drawn by tatarajo, like `XMonad.Prompt`.
+///
+/// It takes the keyboard until the text is submitted with `Return` or cancelled with `Escape` or
+/// `Config::keyseq_cancel`. Supported editing keys are Emacs-like, e.g. `C-a`, `C-k` and `C-w`, and `Up`/`Down` for
+/// history.
+#[derive(Clone)]
+pub struct Prompt {
+    message: String,
+    history_key: Option<String>,
+    completion: Option<Arc<CompletionFn>>,
+    on_submit: Arc<SubmitFn>,
+}
+
+impl std::fmt::Debug for Prompt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Prompt")
+            .field("message", &self.message)
+            .field("history_key", &self.history_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Prompt {
+    /// `on_submit` is called with the submitted text. It is executed as an action, so it can do anything an
+    /// `ActionFnI` can do.
+    pub fn new<F>(message: impl ToString, on_submit: F) -> Self
+    where
+        F: Fn(&mut TatarajoState, String) -> Result<(), ActionError> + 'static,
+    {
+        Self {
+            message: message.to_string(),
+            history_key: None,
+            completion: None,
+            on_submit: Arc::new(on_submit),
+        }
+    }
+
+    /// Remembers submitted texts. Prompts with the same key share the history.
+    pub fn with_history(mut self, key: impl ToString) -> Self {
+        self.history_key = Some(key.to_string());
+        self
+    }
+
+    /// `completion` returns candidates that replace the text on `Tab`.
+    pub fn with_completion<F>(mut self, completion: F) -> Self
+    where
+        F: Fn(&str) -> Vec<String> + 'static,
+    {
+        self.completion = Some(Arc::new(completion));
+        self
+    }
+}
+
+impl TatarajoState {
+    /// Shows a prompt. It replaces the current prompt or window picker if any.
+    pub fn start_prompt(&mut self, prompt: Prompt) {
+        let history = prompt
+            .history_key
+            .as_ref()
+            .and_then(|key| self.inner.prompt_history.get(key))
+            .cloned()
+            .unwrap_or_default();
+        let capture = PromptCapture {
+            prompt,
+            editor: LineEditor::default(),
+            history,
+            history_index: None,
+            candidates: vec![],
+        };
+        self.start_key_capture(Box::new(capture));
+    }
+}
+
+/// Executes `Prompt::on_submit`.
+#[derive(Debug, Clone)]
+struct ActionPromptSubmit {
+    prompt: Prompt,
+    text: String,
+}
+
+impl ActionFnI for ActionPromptSubmit {
+    fn exec(&self, state: &mut TatarajoState) -> Result<(), ActionError> {
+        if let Some(key) = &self.prompt.history_key {
+            let history = state.inner.prompt_history.entry(key.clone()).or_default();
+            history.retain(|text| *text != self.text);
+            history.insert(0, self.text.clone());
+        }
+
+        (self.prompt.on_submit)(state, self.text.clone())
+    }
+}
+
+struct PromptCapture {
+    prompt: Prompt,
+    editor: LineEditor,
+    /// Most recent first.
+    history: Vec<String>,
+    history_index: Option<usize>,
+    candidates: Vec<String>,
+}
+
+impl PromptCapture {
+    fn complete(&mut self) {
+        let Some(completion) = &self.prompt.completion else {
+            return;
+        };
+        let candidates = completion(&self.editor.text());
+        if let Some(prefix) = common_prefix(&candidates) {
+            if prefix.chars().count() > self.editor.text().chars().count() {
+                self.editor.set_text(prefix);
+            }
+        }
+        self.candidates = if candidates.len() > 1 {
+            candidates
+        } else {
+            vec![]
+        };
+    }
+
+    fn move_history(&mut self, older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) if !self.history.is_empty() => Some(0),
+            (None, _) => return,
+            (Some(i), true) => Some((i + 1).min(self.history.len() - 1)),
+            (Some(0), false) => None,
+            (Some(i), false) => Some(i - 1),
+        };
+        self.history_index = index;
+        let text = index.map(|i| self.history[i].clone()).unwrap_or_default();
+        self.editor.set_text(&text);
+    }
+}
+
+impl KeyCapture for PromptCapture {
+    fn on_key(&mut self, key: &Key) -> KeyCaptureResult {
+        let ctrl = key.modmask.contains(ModMask::CONTROL);
+        match (ctrl, key.keysym) {
+            (false, Keysym::Return | Keysym::KP_Enter) | (true, Keysym::m) => {
+                let action = ActionPromptSubmit {
+                    prompt: self.prompt.clone(),
+                    text: self.editor.text(),
+                };
+                return KeyCaptureResult::Finish(Some(action.into_action()));
+            }
+            (false, Keysym::Escape) => return KeyCaptureResult::Finish(None),
+            (false, Keysym::Tab) | (true, Keysym::i) => {
+                self.complete();
+                return KeyCaptureResult::Continue;
+            }
+            (false, Keysym::Up) | (true, Keysym::p) => self.move_history(true),
+            (false, Keysym::Down) | (true, Keysym::n) => self.move_history(false),
+            (false, Keysym::Left) | (true, Keysym::b) => self.editor.backward_char(),
+            (false, Keysym::Right) | (true, Keysym::f) => self.editor.forward_char(),
+            (false, Keysym::Home) | (true, Keysym::a) => self.editor.beginning_of_line(),
+            (false, Keysym::End) | (true, Keysym::e) => self.editor.end_of_line(),
+            (false, Keysym::BackSpace) | (true, Keysym::h) => self.editor.delete_backward_char(),
+            (false, Keysym::Delete) | (true, Keysym::d) => self.editor.delete_char(),
+            (true, Keysym::k) => self.editor.kill_line(),
+            (true, Keysym::u) => self.editor.kill_whole_line(),
+            (true, Keysym::w) => self.editor.backward_kill_word(),
+            (false, keysym) => {
+                let other_mods = ModMask::MOD1 | ModMask::MOD4 | ModMask::MOD5;
+                let Some(c) = char::from_u32(xkb::keysym_to_utf32(keysym)) else {
+                    return KeyCaptureResult::Continue;
+                };
+                if key.modmask.intersects(other_mods) || c.is_control() {
+                    return KeyCaptureResult::Continue;
+                }
+                self.editor.insert(c);
+            }
+            (true, _) => return KeyCaptureResult::Continue,
+        }
+        self.candidates.clear();
+        KeyCaptureResult::Continue
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{}{}", self.prompt.message, self.editor.display())];
+        lines.extend(
+            self.candidates
+                .iter()
+                .take(MAX_CANDIDATES)
+                .map(|c| format!("  {}", c)),
+        );
+        if self.candidates.len() > MAX_CANDIDATES {
+            lines.push(format!(
+                "  ... and {} more",
+                self.candidates.len() - MAX_CANDIDATES
+            ));
+        }
+        lines
+    }
+}
+
+/// A single line text with a cursor.
+#[derive(Debug, Default)]
+struct LineEditor {
+    chars: Vec<char>,
+    /// Index in `chars`.
+    cursor: usize,
+}
+
+impl LineEditor {
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    /// Returns the text with a cursor.
+    fn display(&self) -> String {
+        let mut s = self.chars[..self.cursor].iter().collect::<String>();
+        s.push('_');
+        s.extend(&self.chars[self.cursor..]);
+        s
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn backward_char(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    fn forward_char(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    fn beginning_of_line(&mut self) {
+        self.cursor = 0;
+    }
+
+    fn end_of_line(&mut self) {
+        self.cursor = self.chars.len();
+    }
+
+    fn delete_backward_char(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn delete_char(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn kill_line(&mut self) {
+        self.chars.truncate(self.cursor);
+    }
+
+    fn kill_whole_line(&mut self) {
+        self.chars.clear();
+        self.cursor = 0;
+    }
+
+    fn backward_kill_word(&mut self) {
+        let mut i = self.cursor;
+        while i > 0 && self.chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !self.chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        self.chars.drain(i..self.cursor);
+        self.cursor = i;
+    }
+}
+
+fn common_prefix(candidates: &[String]) -> Option<&str> {
+    let (first, rest) = candidates.split_first()?;
+    let mut len = first.len();
+    for c in rest {
+        len = first
+            .char_indices()
+            .zip(c.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+    Some(&first[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_line_editor() {
+        let mut editor = LineEditor::default();
+        for c in "foo bar baz".chars() {
+            editor.insert(c);
+        }
+        assert_eq!(editor.display(), "foo bar baz_");
+
+        editor.backward_kill_word();
+        assert_eq!(editor.display(), "foo bar _");
+
+        editor.beginning_of_line();
+        editor.forward_char();
+        editor.delete_char();
+        assert_eq!(editor.display(), "f_o bar ");
+
+        editor.delete_backward_char();
+        editor.delete_backward_char();
+        assert_eq!(editor.display(), "_o bar ");
+
+        editor.end_of_line();
+        editor.backward_char();
+        editor.backward_char();
+        editor.kill_line();
+        assert_eq!(editor.text(), "o ba");
+
+        editor.kill_whole_line();
+        assert_eq!(editor.display(), "_");
+    }
+
+    #[rstest(
+        candidates,
+        expected,
+        case(vec![], None),
+        case(vec!["firefox"], Some("firefox")),
+        case(vec!["firefox", "fish", "fi"], Some("fi")),
+        case(vec!["alacritty", "emacs"], Some("")),
+        case(vec!["日本語", "日本"], Some("日本"))
+    )]
+    #[trace]
+    fn test_common_prefix(candidates: Vec<&str>, expected: Option<&str>) {
+        let candidates = candidates.into_iter().map(String::from).collect::<Vec<_>>();
+        assert_eq!(common_prefix(&candidates), expected);
+    }
+}
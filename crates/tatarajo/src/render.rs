@@ -1,8 +1,10 @@
 use crate::pointer::{PointerRenderElement, CLEAR_COLOR};
+use crate::session_lock::{SessionLockState, LOCKED_CLEAR_COLOR};
 use crate::view::window::WindowRenderElement;
 use smithay::backend::renderer::damage::{
     Error as OutputDamageTrackerError, OutputDamageTracker, RenderOutputResult,
 };
+use smithay::backend::renderer::element::memory::MemoryRenderBufferRenderElement;
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::{RenderElement, Wrap};
 use smithay::backend::renderer::{ImportAll, ImportMem, Renderer};
@@ -17,6 +19,7 @@ where
 {
     Pointer(PointerRenderElement<R>),
     Surface(WaylandSurfaceRenderElement<R>),
+    Overlay(MemoryRenderBufferRenderElement<R>),
 }
 
 #[thin_delegate::derive_delegate(external_trait_def = crate::external_trait_def::smithay::backend::renderer::element)]
@@ -45,6 +48,7 @@ where
         match self {
             Self::Pointer(arg0) => f.debug_tuple("Pointer").field(arg0).finish(),
             Self::Surface(arg0) => f.debug_tuple("Surface").field(arg0).finish(),
+            Self::Overlay(arg0) => f.debug_tuple("Overlay").field(arg0).finish(),
         }
     }
 }
@@ -97,10 +101,11 @@ where
     }
 }
 
-pub fn output_elements<R>(
+pub(crate) fn output_elements<R>(
     renderer: &mut R,
     output: &Output,
     space: &Space<crate::view::window::Window>,
+    session_lock: &SessionLockState,
     custom_elements: Vec<CustomRenderElement<R>>,
 ) -> (
     Vec<OutputRenderElement<R, WindowRenderElement<R>>>,
@@ -110,6 +115,22 @@ where
     R: Renderer + ImportAll + ImportMem,
     R::TextureId: Clone + 'static,
 {
+    if session_lock.is_locked() {
+        // Nothing other than the pointer and the lock surface is shown.
+        let mut output_render_elements = custom_elements
+            .into_iter()
+            .filter(|element| matches!(element, CustomRenderElement::Pointer(_)))
+            .map(OutputRenderElement::from)
+            .collect::<Vec<_>>();
+        output_render_elements.extend(
+            session_lock
+                .render_elements(renderer, output)
+                .into_iter()
+                .map(OutputRenderElement::from),
+        );
+        return (output_render_elements, LOCKED_CLEAR_COLOR);
+    }
+
     let mut output_render_elements = custom_elements
         .into_iter()
         .map(OutputRenderElement::from)
@@ -124,10 +145,11 @@ where
 }
 
 #[allow(clippy::too_many_arguments)]
-pub fn render_output<R>(
+pub(crate) fn render_output<R>(
     renderer: &mut R,
     output: &Output,
     space: &Space<crate::view::window::Window>,
+    session_lock: &SessionLockState,
     custom_elements: Vec<CustomRenderElement<R>>,
     damage_tracker: &mut OutputDamageTracker,
     age: usize,
@@ -136,6 +158,7 @@ where
     R: Renderer + ImportAll + ImportMem,
     R::TextureId: Clone + 'static,
 {
-    let (elements, clear_color) = output_elements(renderer, output, space, custom_elements);
+    let (elements, clear_color) =
+        output_elements(renderer, output, space, session_lock, custom_elements);
     damage_tracker.render_output(renderer, age, &elements, clear_color)
 }
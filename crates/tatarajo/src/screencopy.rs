@@ -0,0 +1,1398 @@
+//! Screen capture protocols: `wlr-screencopy-unstable-v1`, and `ext-image-copy-capture-v1` with
+//! `ext-image-capture-source-v1` for outputs.
+//!
+//! Requests are queued and executed by backends when they render the output, with the same elements as
+//! `render::output_elements()`. See `InnerState::render_captures()`.
+
+use crate::render::{output_elements, CustomRenderElement};
+use crate::state::{ClientState, InnerState, TatarajoState};
+use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::allocator::{Buffer as _, Fourcc, Modifier};
+use smithay::backend::renderer::damage::OutputDamageTracker;
+use smithay::backend::renderer::element::utils::{Relocate, RelocateRenderElement};
+use smithay::backend::renderer::element::RenderElement;
+use smithay::backend::renderer::{
+    buffer_type, Bind, BufferType, ExportMem, ImportAll, ImportMem, Offscreen, Renderer, Texture,
+};
+use smithay::output::Output;
+use smithay::reexports::rustix::fs::Dev;
+use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_frame_v1::{
+    self, ZwlrScreencopyFrameV1,
+};
+use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::{
+    self, ZwlrScreencopyManagerV1,
+};
+use smithay::reexports::wayland_server::backend::{ClientId, ObjectId};
+use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
+use smithay::reexports::wayland_server::protocol::wl_shm;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use smithay::utils::{Buffer, Logical, Physical, Rectangle, Size, Transform};
+use smithay::wayland::dmabuf::get_dmabuf;
+use smithay::wayland::shm::{with_buffer_contents, with_buffer_contents_mut};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use wayland_protocols::ext::image_capture_source::v1::server::ext_image_capture_source_v1::ExtImageCaptureSourceV1;
+use wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::{
+    self, ExtOutputImageCaptureSourceManagerV1,
+};
+use wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_cursor_session_v1::{
+    self, ExtImageCopyCaptureCursorSessionV1,
+};
+use wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_frame_v1::{
+    self, ExtImageCopyCaptureFrameV1, FailureReason,
+};
+use wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::{
+    self, ExtImageCopyCaptureManagerV1,
+};
+use wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_session_v1::{
+    self, ExtImageCopyCaptureSessionV1,
+};
+
+const WLR_VERSION: u32 = 3;
+const EXT_VERSION: u32 = 1;
+/// Format of captured frames, which every client supports.
+const FORMAT: Fourcc = Fourcc::Xrgb8888;
+const SHM_FORMAT: wl_shm::Format = wl_shm::Format::Xrgb8888;
+
+pub(crate) struct ScreencopyState {
+    pending: Vec<PendingCapture>,
+    /// Used to compute damage since the last capture.
+    damage_trackers: HashMap<DamageKey, DamageTracker>,
+    /// `None` if the backend doesn't support capture to dmabufs.
+    dmabuf: Option<DmabufConstraints>,
+}
+
+struct DmabufConstraints {
+    device: Dev,
+    modifiers: Vec<Modifier>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum DamageKey {
+    /// Per wlr manager, output name and region.
+    Wlr(ObjectId, String, (i32, i32, i32, i32)),
+    /// Per ext session.
+    Ext(ObjectId),
+}
+
+struct DamageTracker {
+    size: Size<i32, Physical>,
+    transform: Transform,
+    tracker: OutputDamageTracker,
+}
+
+/// A capture waiting for the output to be rendered.
+pub(crate) struct PendingCapture {
+    output: Output,
+    /// In the physical coordinate of the output after the output transform is applied, i.e. as seen on the screen.
+    region: Rectangle<i32, Physical>,
+    buffer: WlBuffer,
+    overlay_cursor: bool,
+    /// Waits until the region is damaged.
+    wait_for_damage: bool,
+    damage_key: DamageKey,
+    frame: CaptureFrame,
+}
+
+enum CaptureFrame {
+    Wlr {
+        frame: ZwlrScreencopyFrameV1,
+        with_damage: bool,
+    },
+    Ext(ExtImageCopyCaptureFrameV1),
+}
+
+#[derive(Debug, thiserror::Error)]
+enum CaptureError {
+    #[error("unsupported buffer type")]
+    UnsupportedBuffer,
+    #[error("failed to access the buffer: {0}")]
+    BufferAccess(String),
+    #[error("rendering failed: {0}")]
+    Render(String),
+}
+
+fn render_error(err: impl std::fmt::Debug) -> CaptureError {
+    CaptureError::Render(format!("{:?}", err))
+}
+
+impl ScreencopyState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        display_handle.create_global::<TatarajoState, ZwlrScreencopyManagerV1, _>(WLR_VERSION, ());
+        display_handle.create_global::<TatarajoState, ExtOutputImageCaptureSourceManagerV1, _>(
+            EXT_VERSION,
+            (),
+        );
+        display_handle
+            .create_global::<TatarajoState, ExtImageCopyCaptureManagerV1, _>(EXT_VERSION, ());
+        Self {
+            pending: vec![],
+            damage_trackers: HashMap::new(),
+            dmabuf: None,
+        }
+    }
+
+    /// Enables capture to dmabufs that the renderer of `device` can render to.
+    pub fn set_dmabuf_constraints(&mut self, device: Dev, modifiers: Vec<Modifier>) {
+        self.dmabuf = (!modifiers.is_empty()).then_some(DmabufConstraints { device, modifiers });
+    }
+
+    pub fn has_pending(&self, output: &Output) -> bool {
+        self.pending.iter().any(|capture| capture.output == *output)
+    }
+
+    /// Fails pending captures of `output`, which won't be rendered, e.g. it is turned off.
+    pub fn fail_pending(&mut self, output: &Output) {
+        self.pending.retain(|capture| {
+            if capture.output != *output {
+                return true;
+            }
+            capture.frame.failed();
+            false
+        });
+    }
+
+    /// Executes a capture. It is queued again if it waits for damage and the region is not damaged.
+    fn capture<R, T, E>(
+        &mut self,
+        renderer: &mut R,
+        capture: PendingCapture,
+        elements: Vec<E>,
+        clear_color: [f32; 4],
+        time: Duration,
+    ) where
+        R: Renderer + Bind<Dmabuf> + Offscreen<T> + Bind<T> + ExportMem,
+        E: RenderElement<R>,
+    {
+        let scale = capture.output.current_scale().fractional_scale();
+        let transform = capture.output.current_transform();
+        let region = capture.region;
+        let buffer_size = transform.transform_size(region.size);
+        let elements = elements
+            .into_iter()
+            .map(|element| {
+                RelocateRenderElement::from_element(
+                    element,
+                    region.loc.upscale(-1),
+                    Relocate::Relative,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let tracker = self.damage_trackers.entry(capture.damage_key.clone());
+        let tracker = tracker.or_insert_with(|| DamageTracker {
+            size: buffer_size,
+            transform,
+            tracker: OutputDamageTracker::new(buffer_size, scale, transform),
+        });
+        if (tracker.size, tracker.transform) != (buffer_size, transform) {
+            *tracker = DamageTracker {
+                size: buffer_size,
+                transform,
+                tracker: OutputDamageTracker::new(buffer_size, scale, transform),
+            };
+        }
+        let damage = match tracker.tracker.damage_output(1, &elements) {
+            Ok((damage, _)) => damage.cloned(),
+            Err(err) => {
+                warn!("failed to compute damage of capture: {:?}", err);
+                capture.frame.failed();
+                return;
+            }
+        };
+        if capture.wait_for_damage && damage.is_none() {
+            self.pending.push(capture);
+            return;
+        }
+
+        let result = render_to_buffer::<R, T, _>(
+            renderer,
+            &capture.buffer,
+            buffer_size,
+            scale,
+            transform,
+            &elements,
+            clear_color,
+        );
+        match result {
+            Ok(()) => {
+                // The buffer holds the screen rotated back by the output transform.
+                let damage = damage
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|rect| {
+                        rect.to_logical(1).to_buffer(
+                            1,
+                            transform.invert(),
+                            &region.size.to_logical(1),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                capture.frame.ready(transform, &damage, time);
+            }
+            Err(err) => {
+                warn!(
+                    "failed to capture output {}: {}",
+                    capture.output.name(),
+                    err
+                );
+                capture.frame.failed();
+            }
+        }
+    }
+
+    fn send_session_constraints(
+        &self,
+        session: &ExtImageCopyCaptureSessionV1,
+        size: Size<i32, Physical>,
+    ) {
+        session.buffer_size(size.w as u32, size.h as u32);
+        session.shm_format(SHM_FORMAT);
+        if let Some(dmabuf) = &self.dmabuf {
+            session.dmabuf_device(dmabuf.device.to_ne_bytes().to_vec());
+            let modifiers = dmabuf
+                .modifiers
+                .iter()
+                .flat_map(|&modifier| u64::from(modifier).to_ne_bytes())
+                .collect();
+            session.dmabuf_format(FORMAT as u32, modifiers);
+        }
+        session.done();
+    }
+}
+
+impl InnerState {
+    /// Executes pending captures of `output`. Backends call it when they render the output.
+    ///
+    /// `cursor_elements` returns elements of the pointer, used for captures that overlay the cursor. `T` is a type of
+    /// offscreen buffers used for capture to SHM buffers.
+    pub(crate) fn render_captures<R, T, F>(
+        &mut self,
+        renderer: &mut R,
+        output: &Output,
+        mut cursor_elements: F,
+    ) where
+        R: Renderer + ImportAll + ImportMem + Bind<Dmabuf> + Offscreen<T> + Bind<T> + ExportMem,
+        R::TextureId: Texture + Clone + 'static,
+        F: FnMut(&mut R, &InnerState) -> Vec<CustomRenderElement<R>>,
+    {
+        if !self.screencopy_state.has_pending(output) {
+            return;
+        }
+
+        let (captures, rest) = std::mem::take(&mut self.screencopy_state.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|capture| capture.output == *output);
+        self.screencopy_state.pending = rest;
+
+        let time = self.clock.now().into();
+        for capture in captures {
+            if !capture.frame.is_alive() {
+                continue;
+            }
+
+            let mut custom_elements = if capture.overlay_cursor {
+                cursor_elements(renderer, self)
+            } else {
+                vec![]
+            };
+            custom_elements.extend(self.overlay_elements(renderer, output));
+            let (elements, clear_color) = output_elements(
+                renderer,
+                output,
+                &self.space,
+                &self.session_lock_state,
+                custom_elements,
+            );
+            self.screencopy_state.capture::<R, T, _>(
+                renderer,
+                capture,
+                elements,
+                clear_color,
+                time,
+            );
+        }
+    }
+}
+
+fn render_to_buffer<R, T, E>(
+    renderer: &mut R,
+    buffer: &WlBuffer,
+    size: Size<i32, Physical>,
+    scale: f64,
+    transform: Transform,
+    elements: &[E],
+    clear_color: [f32; 4],
+) -> Result<(), CaptureError>
+where
+    R: Renderer + Bind<Dmabuf> + Offscreen<T> + Bind<T> + ExportMem,
+    E: RenderElement<R>,
+{
+    // Every frame is fully redrawn, because the content of the buffer is unknown.
+    let mut tracker = OutputDamageTracker::new(size, scale, transform);
+    let buffer_size = size.to_logical(1).to_buffer(1, Transform::Normal);
+
+    match buffer_type(buffer) {
+        Some(BufferType::Dma) => {
+            let dmabuf = get_dmabuf(buffer)
+                .map_err(|err| CaptureError::BufferAccess(err.to_string()))?
+                .clone();
+            renderer.bind(dmabuf).map_err(render_error)?;
+            let result = tracker
+                .render_output(renderer, 0, elements, clear_color)
+                .map_err(render_error)?;
+            let _ = result.sync.wait();
+        }
+        Some(BufferType::Shm) => {
+            let target: T = renderer
+                .create_buffer(FORMAT, buffer_size)
+                .map_err(render_error)?;
+            renderer.bind(target).map_err(render_error)?;
+            tracker
+                .render_output(renderer, 0, elements, clear_color)
+                .map_err(render_error)?;
+            let mapping = renderer
+                .copy_framebuffer(Rectangle::from_loc_and_size((0, 0), buffer_size), FORMAT)
+                .map_err(render_error)?;
+            let pixels = renderer.map_texture(&mapping).map_err(render_error)?;
+            copy_to_shm(buffer, pixels, buffer_size)?;
+        }
+        _ => return Err(CaptureError::UnsupportedBuffer),
+    }
+
+    Ok(())
+}
+
+/// Copies tightly packed pixels of `FORMAT` to an SHM buffer, which may have a larger stride.
+fn copy_to_shm(
+    buffer: &WlBuffer,
+    pixels: &[u8],
+    size: Size<i32, Buffer>,
+) -> Result<(), CaptureError> {
+    let row_len = size.w as usize * 4;
+    with_buffer_contents_mut(buffer, |ptr, len, data| {
+        let offset = data.offset as usize;
+        let stride = data.stride as usize;
+        if offset + stride * (size.h as usize - 1) + row_len > len
+            || pixels.len() < row_len * size.h as usize
+        {
+            return Err(CaptureError::BufferAccess(
+                "buffer is too small".to_string(),
+            ));
+        }
+        for (y, row) in pixels
+            .chunks_exact(row_len)
+            .take(size.h as usize)
+            .enumerate()
+        {
+            // Safety: The range is checked above.
+            unsafe {
+                std::ptr::copy_nonoverlapping(row.as_ptr(), ptr.add(offset + stride * y), row_len);
+            }
+        }
+        Ok(())
+    })
+    .map_err(|err| CaptureError::BufferAccess(err.to_string()))?
+}
+
+/// Returns true iff `buffer` can be used for a capture of `size`.
+fn validate_buffer(buffer: &WlBuffer, size: Size<i32, Physical>) -> bool {
+    let size = size.to_logical(1).to_buffer(1, Transform::Normal);
+    match buffer_type(buffer) {
+        Some(BufferType::Shm) => with_buffer_contents(buffer, |_, _, data| {
+            data.format == SHM_FORMAT
+                && data.width == size.w
+                && data.height == size.h
+                && data.stride >= size.w * 4
+        })
+        .unwrap_or(false),
+        Some(BufferType::Dma) => get_dmabuf(buffer)
+            .map(|dmabuf| dmabuf.format().code == FORMAT && dmabuf.size() == size)
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Returns the captured region in the physical coordinate of the output after the output transform is applied, i.e. as
+/// seen on the screen. `region` is in the logical coordinate of the output, and the whole output if it is `None`.
+fn capture_region(
+    output: &Output,
+    region: Option<Rectangle<i32, Logical>>,
+) -> Option<Rectangle<i32, Physical>> {
+    let mode = output.current_mode()?;
+    let whole =
+        Rectangle::from_loc_and_size((0, 0), output.current_transform().transform_size(mode.size));
+    let Some(region) = region else {
+        return Some(whole);
+    };
+    let scale = output.current_scale().fractional_scale();
+    region
+        .to_f64()
+        .to_physical(scale)
+        .to_i32_round()
+        .intersection(whole)
+        .filter(|region| !region.is_empty())
+}
+
+impl CaptureFrame {
+    fn is_alive(&self) -> bool {
+        match self {
+            Self::Wlr { frame, .. } => frame.is_alive(),
+            Self::Ext(frame) => frame.is_alive(),
+        }
+    }
+
+    fn ready(&self, transform: Transform, damage: &[Rectangle<i32, Buffer>], time: Duration) {
+        let secs = time.as_secs();
+        let (secs_hi, secs_lo) = ((secs >> 32) as u32, secs as u32);
+        let nanos = time.subsec_nanos();
+        match self {
+            Self::Wlr { frame, with_damage } => {
+                frame.flags(zwlr_screencopy_frame_v1::Flags::empty());
+                if *with_damage {
+                    for rect in damage {
+                        frame.damage(
+                            rect.loc.x as u32,
+                            rect.loc.y as u32,
+                            rect.size.w as u32,
+                            rect.size.h as u32,
+                        );
+                    }
+                }
+                frame.ready(secs_hi, secs_lo, nanos);
+            }
+            Self::Ext(frame) => {
+                frame.transform(transform.into());
+                for rect in damage {
+                    frame.damage(rect.loc.x, rect.loc.y, rect.size.w, rect.size.h);
+                }
+                frame.presentation_time(secs_hi, secs_lo, nanos);
+                frame.ready();
+            }
+        }
+    }
+
+    fn failed(&self) {
+        match self {
+            Self::Wlr { frame, .. } => frame.failed(),
+            Self::Ext(frame) => frame.failed(FailureReason::Unknown),
+        }
+    }
+}
+
+fn can_view(client: &Client) -> bool {
+    client
+        .get_data::<ClientState>()
+        .map_or(true, |client_state| client_state.security_context.is_none())
+}
+
+// wlr-screencopy
+
+/// `None` after the frame is copied or failed.
+type WlrFrameData = Mutex<Option<WlrFrameInfo>>;
+
+struct WlrFrameInfo {
+    manager_id: ObjectId,
+    output: Output,
+    region: Rectangle<i32, Physical>,
+    overlay_cursor: bool,
+}
+
+impl GlobalDispatch<ZwlrScreencopyManagerV1, ()> for TatarajoState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrScreencopyManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ZwlrScreencopyManagerV1, ()> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrScreencopyManagerV1,
+        request: zwlr_screencopy_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let (frame, overlay_cursor, output, region) = match request {
+            zwlr_screencopy_manager_v1::Request::CaptureOutput {
+                frame,
+                overlay_cursor,
+                output,
+            } => (frame, overlay_cursor, output, None),
+            zwlr_screencopy_manager_v1::Request::CaptureOutputRegion {
+                frame,
+                overlay_cursor,
+                output,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let region = Rectangle::from_loc_and_size((x, y), (width, height));
+                (frame, overlay_cursor, output, Some(region))
+            }
+            _ => return,
+        };
+
+        let info = Output::from_resource(&output).and_then(|output| {
+            let region = capture_region(&output, region)?;
+            Some(WlrFrameInfo {
+                manager_id: resource.id(),
+                output,
+                region,
+                overlay_cursor: overlay_cursor != 0,
+            })
+        });
+        let Some(info) = info else {
+            let frame = data_init.init(frame, Mutex::new(None));
+            frame.failed();
+            return;
+        };
+
+        let size = info
+            .output
+            .current_transform()
+            .transform_size(info.region.size);
+        let frame = data_init.init(frame, Mutex::new(Some(info)));
+        frame.buffer(SHM_FORMAT, size.w as u32, size.h as u32, size.w as u32 * 4);
+        if frame.version() >= 3 {
+            if state.inner.screencopy_state.dmabuf.is_some() {
+                frame.linux_dmabuf(FORMAT as u32, size.w as u32, size.h as u32);
+            }
+            frame.buffer_done();
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrScreencopyManagerV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .screencopy_state
+            .damage_trackers
+            .retain(|key, _| !matches!(key, DamageKey::Wlr(id, ..) if *id == resource.id()));
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, WlrFrameData> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrScreencopyFrameV1,
+        request: zwlr_screencopy_frame_v1::Request,
+        data: &WlrFrameData,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        let (buffer, with_damage) = match request {
+            zwlr_screencopy_frame_v1::Request::Copy { buffer } => (buffer, false),
+            zwlr_screencopy_frame_v1::Request::CopyWithDamage { buffer } => (buffer, true),
+            _ => return,
+        };
+
+        let Some(info) = data.lock().unwrap().take() else {
+            resource.post_error(
+                zwlr_screencopy_frame_v1::Error::AlreadyUsed,
+                "the frame is already used",
+            );
+            return;
+        };
+        let size = info
+            .output
+            .current_transform()
+            .transform_size(info.region.size);
+        if !validate_buffer(&buffer, size) {
+            resource.post_error(
+                zwlr_screencopy_frame_v1::Error::InvalidBuffer,
+                "the buffer doesn't match the frame",
+            );
+            return;
+        }
+
+        if !state.inner.output_power_state.is_on(&info.output) {
+            // The output isn't rendered until it is turned on.
+            resource.failed();
+            return;
+        }
+
+        let region = info.region;
+        let damage_key = DamageKey::Wlr(
+            info.manager_id,
+            info.output.name(),
+            (region.loc.x, region.loc.y, region.size.w, region.size.h),
+        );
+        state.inner.screencopy_state.pending.push(PendingCapture {
+            output: info.output,
+            region,
+            buffer,
+            overlay_cursor: info.overlay_cursor,
+            wait_for_damage: with_damage,
+            damage_key,
+            frame: CaptureFrame::Wlr {
+                frame: resource.clone(),
+                with_damage,
+            },
+        });
+    }
+}
+
+// ext-image-copy-capture
+
+pub(crate) struct SessionData {
+    /// `None` if the session is stopped.
+    output: Option<Output>,
+    paint_cursors: bool,
+}
+
+pub(crate) struct FrameData {
+    session: ExtImageCopyCaptureSessionV1,
+    buffer: Mutex<Option<WlBuffer>>,
+    captured: AtomicBool,
+}
+
+impl GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ()> for TatarajoState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtOutputImageCaptureSourceManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ExtOutputImageCaptureSourceManagerV1, ()> for TatarajoState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtOutputImageCaptureSourceManagerV1,
+        request: ext_output_image_capture_source_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let ext_output_image_capture_source_manager_v1::Request::CreateSource {
+            source,
+            output,
+        } = request
+        {
+            data_init.init(source, Output::from_resource(&output));
+        }
+    }
+}
+
+impl Dispatch<ExtImageCaptureSourceV1, Option<Output>> for TatarajoState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtImageCaptureSourceV1,
+        _request: <ExtImageCaptureSourceV1 as Resource>::Request,
+        _data: &Option<Output>,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // `destroy` is the only request, which is a destructor.
+    }
+}
+
+impl GlobalDispatch<ExtImageCopyCaptureManagerV1, ()> for TatarajoState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtImageCopyCaptureManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureManagerV1, ()> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ExtImageCopyCaptureManagerV1,
+        request: ext_image_copy_capture_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_image_copy_capture_manager_v1::Request::CreateSession {
+                session,
+                source,
+                options,
+            } => {
+                use ext_image_copy_capture_manager_v1::Options;
+
+                let output = source.data::<Option<Output>>().cloned().flatten();
+                let region = output
+                    .as_ref()
+                    .and_then(|output| capture_region(output, None));
+                let paint_cursors = options
+                    .into_result()
+                    .is_ok_and(|options| options.contains(Options::PaintCursors));
+                let session = data_init.init(
+                    session,
+                    SessionData {
+                        output: output.clone(),
+                        paint_cursors,
+                    },
+                );
+                match (output, region) {
+                    (Some(output), Some(region)) => {
+                        let size = output.current_transform().transform_size(region.size);
+                        state
+                            .inner
+                            .screencopy_state
+                            .send_session_constraints(&session, size);
+                    }
+                    _ => session.stopped(),
+                }
+            }
+            ext_image_copy_capture_manager_v1::Request::CreatePointerCursorSession {
+                session,
+                ..
+            } => {
+                // Cursor sessions are not supported. Their capture sessions are stopped immediately.
+                data_init.init(session, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureCursorSessionV1, ()> for TatarajoState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtImageCopyCaptureCursorSessionV1,
+        request: ext_image_copy_capture_cursor_session_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let ext_image_copy_capture_cursor_session_v1::Request::GetCaptureSession { session } =
+            request
+        {
+            let session = data_init.init(
+                session,
+                SessionData {
+                    output: None,
+                    paint_cursors: false,
+                },
+            );
+            session.stopped();
+        }
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureSessionV1, SessionData> for TatarajoState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        resource: &ExtImageCopyCaptureSessionV1,
+        request: ext_image_copy_capture_session_v1::Request,
+        _data: &SessionData,
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let ext_image_copy_capture_session_v1::Request::CreateFrame { frame } = request {
+            data_init.init(
+                frame,
+                FrameData {
+                    session: resource.clone(),
+                    buffer: Mutex::new(None),
+                    captured: AtomicBool::new(false),
+                },
+            );
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ExtImageCopyCaptureSessionV1,
+        _data: &SessionData,
+    ) {
+        state
+            .inner
+            .screencopy_state
+            .damage_trackers
+            .remove(&DamageKey::Ext(resource.id()));
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureFrameV1, FrameData> for TatarajoState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtImageCopyCaptureFrameV1,
+        request: ext_image_copy_capture_frame_v1::Request,
+        data: &FrameData,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_image_copy_capture_frame_v1::Request::AttachBuffer { buffer } => {
+                *data.buffer.lock().unwrap() = Some(buffer);
+            }
+            // Every frame is fully redrawn, so buffer damage is not needed.
+            ext_image_copy_capture_frame_v1::Request::DamageBuffer { .. } => {}
+            ext_image_copy_capture_frame_v1::Request::Capture => {
+                if data.captured.swap(true, Ordering::SeqCst) {
+                    resource.post_error(
+                        ext_image_copy_capture_frame_v1::Error::AlreadyCaptured,
+                        "the frame is already captured",
+                    );
+                    return;
+                }
+                let Some(buffer) = data.buffer.lock().unwrap().clone() else {
+                    resource.post_error(
+                        ext_image_copy_capture_frame_v1::Error::NoBuffer,
+                        "no buffer is attached",
+                    );
+                    return;
+                };
+
+                let Some(session_data) = data.session.data::<SessionData>() else {
+                    resource.failed(FailureReason::Unknown);
+                    return;
+                };
+                let Some(output) = session_data.output.clone() else {
+                    resource.failed(FailureReason::Stopped);
+                    return;
+                };
+                let Some(region) = capture_region(&output, None) else {
+                    data.session.stopped();
+                    resource.failed(FailureReason::Stopped);
+                    return;
+                };
+                let size = output.current_transform().transform_size(region.size);
+                if !validate_buffer(&buffer, size) {
+                    // The output may have been changed.
+                    state
+                        .inner
+                        .screencopy_state
+                        .send_session_constraints(&data.session, size);
+                    resource.failed(FailureReason::BufferConstraints);
+                    return;
+                }
+
+                if !state.inner.output_power_state.is_on(&output) {
+                    // The output isn't rendered until it is turned on.
+                    resource.failed(FailureReason::Unknown);
+                    return;
+                }
+
+                state.inner.screencopy_state.pending.push(PendingCapture {
+                    output,
+                    region,
+                    buffer,
+                    overlay_cursor: session_data.paint_cursors,
+                    wait_for_damage: true,
+                    damage_key: DamageKey::Ext(data.session.id()),
+                    frame: CaptureFrame::Ext(resource.clone()),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smithay::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
+    use smithay::backend::renderer::element::Kind;
+    use smithay::backend::renderer::pixman::{PixmanRenderBuffer, PixmanRenderer};
+    use smithay::output::{Mode, PhysicalProperties, Scale, Subpixel};
+    use smithay::reexports::rustix::fs::{memfd_create, MemfdFlags};
+    use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+    use smithay::reexports::wayland_server::Display;
+    use smithay::wayland::buffer::BufferHandler;
+    use smithay::wayland::compositor::{CompositorClientState, CompositorHandler, CompositorState};
+    use smithay::wayland::output::OutputHandler;
+    use smithay::wayland::shm::{ShmHandler, ShmState};
+    use std::fs::File;
+    use std::os::fd::AsFd;
+    use std::os::unix::fs::FileExt;
+    use std::os::unix::net::UnixStream;
+    use std::sync::Arc;
+    use wayland_client::protocol::{
+        wl_buffer, wl_output, wl_registry, wl_shm as client_wl_shm, wl_shm_pool,
+    };
+    use wayland_client::{Connection, EventQueue, Proxy, QueueHandle};
+    use wayland_protocols_wlr::screencopy::v1::client::{
+        zwlr_screencopy_frame_v1 as client_frame, zwlr_screencopy_manager_v1 as client_manager,
+    };
+
+    const WIDTH: i32 = 4;
+    const HEIGHT: i32 = 2;
+    const STRIDE: i32 = WIDTH * 4;
+
+    /// A compositor that only has `wl_compositor`, `wl_shm`, `wl_output` and frames of screencopy, in place of
+    /// `TatarajoState`.
+    struct TestServer {
+        compositor_state: CompositorState,
+        shm_state: ShmState,
+        frames: Vec<ZwlrScreencopyFrameV1>,
+    }
+
+    impl CompositorHandler for TestServer {
+        fn compositor_state(&mut self) -> &mut CompositorState {
+            &mut self.compositor_state
+        }
+
+        fn client_compositor_state<'a>(&self, client: &'a Client) -> &'a CompositorClientState {
+            &client.get_data::<ClientState>().unwrap().compositor_state
+        }
+
+        fn commit(&mut self, _surface: &WlSurface) {}
+    }
+
+    smithay::delegate_compositor!(TestServer);
+
+    impl BufferHandler for TestServer {
+        fn buffer_destroyed(&mut self, _buffer: &WlBuffer) {}
+    }
+
+    impl ShmHandler for TestServer {
+        fn shm_state(&self) -> &ShmState {
+            &self.shm_state
+        }
+    }
+
+    smithay::delegate_shm!(TestServer);
+
+    impl OutputHandler for TestServer {}
+
+    smithay::delegate_output!(TestServer);
+
+    impl GlobalDispatch<ZwlrScreencopyManagerV1, ()> for TestServer {
+        fn bind(
+            _state: &mut Self,
+            _display_handle: &DisplayHandle,
+            _client: &Client,
+            resource: New<ZwlrScreencopyManagerV1>,
+            _global_data: &(),
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            data_init.init(resource, ());
+        }
+    }
+
+    impl Dispatch<ZwlrScreencopyManagerV1, ()> for TestServer {
+        fn request(
+            state: &mut Self,
+            _client: &Client,
+            _resource: &ZwlrScreencopyManagerV1,
+            request: zwlr_screencopy_manager_v1::Request,
+            _data: &(),
+            _display_handle: &DisplayHandle,
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            match request {
+                zwlr_screencopy_manager_v1::Request::CaptureOutput { frame, .. }
+                | zwlr_screencopy_manager_v1::Request::CaptureOutputRegion { frame, .. } => {
+                    state.frames.push(data_init.init(frame, ()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    impl Dispatch<ZwlrScreencopyFrameV1, ()> for TestServer {
+        fn request(
+            _state: &mut Self,
+            _client: &Client,
+            _resource: &ZwlrScreencopyFrameV1,
+            _request: zwlr_screencopy_frame_v1::Request,
+            _data: &(),
+            _display_handle: &DisplayHandle,
+            _data_init: &mut DataInit<'_, Self>,
+        ) {
+        }
+    }
+
+    #[derive(Default)]
+    struct TestClient {
+        /// (name, interface, version)
+        globals: Vec<(u32, String, u32)>,
+        damage: Vec<(u32, u32, u32, u32)>,
+        ready: usize,
+        failed: usize,
+    }
+
+    impl TestClient {
+        fn bind<I>(&self, registry: &wl_registry::WlRegistry, qh: &QueueHandle<Self>) -> I
+        where
+            I: Proxy + 'static,
+            Self: wayland_client::Dispatch<I, ()>,
+        {
+            let (name, _, version) = self
+                .globals
+                .iter()
+                .find(|(_, interface, _)| interface == I::interface().name)
+                .unwrap();
+            registry.bind(*name, *version, qh, ())
+        }
+    }
+
+    impl wayland_client::Dispatch<wl_registry::WlRegistry, ()> for TestClient {
+        fn event(
+            state: &mut Self,
+            _proxy: &wl_registry::WlRegistry,
+            event: wl_registry::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {
+            if let wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } = event
+            {
+                state.globals.push((name, interface, version));
+            }
+        }
+    }
+
+    impl wayland_client::Dispatch<client_frame::ZwlrScreencopyFrameV1, ()> for TestClient {
+        fn event(
+            state: &mut Self,
+            _proxy: &client_frame::ZwlrScreencopyFrameV1,
+            event: client_frame::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {
+            match event {
+                client_frame::Event::Damage {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => state.damage.push((x, y, width, height)),
+                client_frame::Event::Ready { .. } => state.ready += 1,
+                client_frame::Event::Failed => state.failed += 1,
+                _ => {}
+            }
+        }
+    }
+
+    wayland_client::delegate_noop!(TestClient: ignore client_wl_shm::WlShm);
+    wayland_client::delegate_noop!(TestClient: wl_shm_pool::WlShmPool);
+    wayland_client::delegate_noop!(TestClient: ignore wl_buffer::WlBuffer);
+    wayland_client::delegate_noop!(TestClient: ignore wl_output::WlOutput);
+    wayland_client::delegate_noop!(TestClient: client_manager::ZwlrScreencopyManagerV1);
+
+    /// Exchanges requests and events until both sides are idle.
+    fn roundtrip(
+        display: &mut Display<TestServer>,
+        server: &mut TestServer,
+        conn: &Connection,
+        queue: &mut EventQueue<TestClient>,
+        client: &mut TestClient,
+    ) {
+        for _ in 0..2 {
+            conn.flush().unwrap();
+            display.dispatch_clients(server).unwrap();
+            display.flush_clients().unwrap();
+            if let Some(guard) = conn.prepare_read() {
+                // The socket is non-blocking, and it fails with `WouldBlock` if there are no events.
+                let _ = guard.read();
+            }
+            queue.dispatch_pending(client).unwrap();
+        }
+    }
+
+    fn pixel(file: &File, x: i32, y: i32) -> [u8; 3] {
+        let mut pixel = [0; 4];
+        file.read_exact_at(&mut pixel, (y * STRIDE + x * 4) as u64)
+            .unwrap();
+        // Xrgb8888 is BGRX in little endian.
+        [pixel[2], pixel[1], pixel[0]]
+    }
+
+    #[test]
+    fn test_capture_to_shm() {
+        let mut display = Display::<TestServer>::new().unwrap();
+        let mut display_handle = display.handle();
+        let mut server = TestServer {
+            compositor_state: CompositorState::new::<TestServer>(&display_handle),
+            shm_state: ShmState::new::<TestServer>(&display_handle, vec![]),
+            frames: vec![],
+        };
+        display_handle.create_global::<TestServer, ZwlrScreencopyManagerV1, _>(WLR_VERSION, ());
+        let output = Output::new(
+            "TEST-1".to_string(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "Test".into(),
+                model: "Test".into(),
+            },
+        );
+        let mode = Mode {
+            size: (WIDTH, HEIGHT).into(),
+            refresh: 60_000,
+        };
+        output.change_current_state(
+            Some(mode),
+            Some(Transform::Normal),
+            Some(Scale::Integer(1)),
+            Some((0, 0).into()),
+        );
+        let _output_global = output.create_global::<TestServer>(&display_handle);
+
+        let (client_stream, server_stream) = UnixStream::pair().unwrap();
+        client_stream.set_nonblocking(true).unwrap();
+        let client = display_handle
+            .insert_client(server_stream, Arc::new(ClientState::default()))
+            .unwrap();
+        let conn = Connection::from_socket(client_stream).unwrap();
+        let mut queue = conn.new_event_queue();
+        let qh = queue.handle();
+        let mut test_client = TestClient::default();
+        let registry = conn.display().get_registry(&qh, ());
+        roundtrip(
+            &mut display,
+            &mut server,
+            &conn,
+            &mut queue,
+            &mut test_client,
+        );
+
+        let shm: client_wl_shm::WlShm = test_client.bind(&registry, &qh);
+        let client_output: wl_output::WlOutput = test_client.bind(&registry, &qh);
+        let manager: client_manager::ZwlrScreencopyManagerV1 = test_client.bind(&registry, &qh);
+        let file = File::from(memfd_create("tatarajo-test", MemfdFlags::CLOEXEC).unwrap());
+        file.set_len((STRIDE * HEIGHT) as u64).unwrap();
+        let pool = shm.create_pool(file.as_fd(), STRIDE * HEIGHT, &qh, ());
+        let buffer = pool.create_buffer(
+            0,
+            WIDTH,
+            HEIGHT,
+            STRIDE,
+            client_wl_shm::Format::Xrgb8888,
+            &qh,
+            (),
+        );
+        // Frames can't be reused, so they are prepared for each capture.
+        for _ in 0..2 {
+            manager.capture_output(0, &client_output, &qh, ());
+        }
+        roundtrip(
+            &mut display,
+            &mut server,
+            &conn,
+            &mut queue,
+            &mut test_client,
+        );
+
+        let buffer = client
+            .object_from_protocol_id::<WlBuffer>(&display_handle, buffer.id().protocol_id())
+            .unwrap();
+        let manager_id = client
+            .object_from_protocol_id::<ZwlrScreencopyManagerV1>(
+                &display_handle,
+                manager.id().protocol_id(),
+            )
+            .unwrap()
+            .id();
+        let region = capture_region(&output, None).unwrap();
+        assert_eq!(
+            region,
+            Rectangle::from_loc_and_size((0, 0), (WIDTH, HEIGHT))
+        );
+        let damage_key = DamageKey::Wlr(manager_id.clone(), output.name(), (0, 0, WIDTH, HEIGHT));
+        let pending_capture =
+            |frame: ZwlrScreencopyFrameV1,
+             region: Rectangle<i32, Physical>,
+             buffer: &WlBuffer,
+             damage_key: &DamageKey| PendingCapture {
+                output: output.clone(),
+                region,
+                buffer: buffer.clone(),
+                overlay_cursor: false,
+                wait_for_damage: false,
+                damage_key: damage_key.clone(),
+                frame: CaptureFrame::Wlr {
+                    frame,
+                    with_damage: true,
+                },
+            };
+
+        let mut state = ScreencopyState {
+            pending: vec![],
+            damage_trackers: HashMap::new(),
+            dmabuf: None,
+        };
+        let mut renderer = PixmanRenderer::new().unwrap();
+        let red = SolidColorBuffer::new((2, 2), [1.0, 0.0, 0.0, 1.0]);
+        let elements = || {
+            vec![SolidColorRenderElement::from_buffer(
+                &red,
+                (0, 0),
+                1.0,
+                1.0,
+                Kind::Unspecified,
+            )]
+        };
+        let blue = [0.0, 0.0, 1.0, 1.0];
+
+        // The first capture is fully damaged.
+        let frame = server.frames.remove(0);
+        state.capture::<PixmanRenderer, PixmanRenderBuffer, _>(
+            &mut renderer,
+            pending_capture(frame, region, &buffer, &damage_key),
+            elements(),
+            blue,
+            Duration::ZERO,
+        );
+        roundtrip(
+            &mut display,
+            &mut server,
+            &conn,
+            &mut queue,
+            &mut test_client,
+        );
+        assert_eq!((test_client.ready, test_client.failed), (1, 0));
+        assert_eq!(
+            test_client.damage,
+            vec![(0, 0, WIDTH as u32, HEIGHT as u32)]
+        );
+        assert_eq!(pixel(&file, 0, 0), [255, 0, 0]);
+        assert_eq!(pixel(&file, 1, 1), [255, 0, 0]);
+        assert_eq!(pixel(&file, 2, 0), [0, 0, 255]);
+        assert_eq!(pixel(&file, 3, 1), [0, 0, 255]);
+
+        // Nothing is damaged if nothing changed.
+        test_client.damage.clear();
+        let frame = server.frames.remove(0);
+        state.capture::<PixmanRenderer, PixmanRenderBuffer, _>(
+            &mut renderer,
+            pending_capture(frame, region, &buffer, &damage_key),
+            elements(),
+            blue,
+            Duration::ZERO,
+        );
+        roundtrip(
+            &mut display,
+            &mut server,
+            &conn,
+            &mut queue,
+            &mut test_client,
+        );
+        assert_eq!((test_client.ready, test_client.failed), (2, 0));
+        assert!(test_client.damage.is_empty());
+        assert!(state.pending.is_empty());
+
+        // With a rotated output, regions are in the rotated coordinate while buffers are in that of the mode.
+        output.change_current_state(None, Some(Transform::_90), None, None);
+        let region =
+            capture_region(&output, Some(Rectangle::from_loc_and_size((0, 1), (2, 3)))).unwrap();
+        assert_eq!(region, Rectangle::from_loc_and_size((0, 1), (2, 3)));
+        let size = output.current_transform().transform_size(region.size);
+        assert_eq!(size, (3, 2).into());
+        let buffer = pool.create_buffer(
+            0,
+            size.w,
+            size.h,
+            STRIDE,
+            client_wl_shm::Format::Xrgb8888,
+            &qh,
+            (),
+        );
+        for _ in 0..2 {
+            manager.capture_output_region(0, &client_output, 0, 1, 2, 3, &qh, ());
+        }
+        roundtrip(
+            &mut display,
+            &mut server,
+            &conn,
+            &mut queue,
+            &mut test_client,
+        );
+        let buffer = client
+            .object_from_protocol_id::<WlBuffer>(&display_handle, buffer.id().protocol_id())
+            .unwrap();
+        assert!(validate_buffer(&buffer, size));
+        let damage_key = DamageKey::Wlr(manager_id, output.name(), (0, 1, 2, 3));
+
+        // The first capture of the region is fully damaged, even if the output isn't.
+        let frame = server.frames.remove(0);
+        state.capture::<PixmanRenderer, PixmanRenderBuffer, _>(
+            &mut renderer,
+            pending_capture(frame, region, &buffer, &damage_key),
+            Vec::<SolidColorRenderElement>::new(),
+            blue,
+            Duration::ZERO,
+        );
+        roundtrip(
+            &mut display,
+            &mut server,
+            &conn,
+            &mut queue,
+            &mut test_client,
+        );
+        assert_eq!((test_client.ready, test_client.failed), (3, 0));
+        assert_eq!(test_client.damage, vec![(0, 0, 3, 2)]);
+
+        // Only the top row of the region is red, which is the left column of the buffer.
+        test_client.damage.clear();
+        let frame = server.frames.remove(0);
+        state.capture::<PixmanRenderer, PixmanRenderBuffer, _>(
+            &mut renderer,
+            pending_capture(frame, region, &buffer, &damage_key),
+            elements(),
+            blue,
+            Duration::ZERO,
+        );
+        roundtrip(
+            &mut display,
+            &mut server,
+            &conn,
+            &mut queue,
+            &mut test_client,
+        );
+        assert_eq!((test_client.ready, test_client.failed), (4, 0));
+        assert_eq!(test_client.damage, vec![(0, 0, 1, 2)]);
+        assert_eq!(pixel(&file, 0, 0), [255, 0, 0]);
+        assert_eq!(pixel(&file, 0, 1), [255, 0, 0]);
+        assert_eq!(pixel(&file, 1, 0), [0, 0, 255]);
+        assert_eq!(pixel(&file, 2, 1), [0, 0, 255]);
+    }
+}
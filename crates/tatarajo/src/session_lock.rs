@@ -0,0 +1,260 @@
+//! `ext-session-lock-v1`, used by screen lockers, e.g. swaylock.
+//!
+//! While the session is locked, outputs show only lock surfaces and all input goes to them. If the locker dies
+//! without unlocking, the session stays locked and outputs are filled with `LOCKED_CLEAR_COLOR`.
+//!
+//! The locker is notified that the session is locked only after every output has shown a locked frame, as the
+//! protocol requires. Backends report it with `SessionLockState::on_locked_frame()`.
+
+use crate::focus::{KeyboardFocusTarget, PointerFocusTarget};
+use crate::render::CustomRenderElement;
+use crate::state::{ClientState, TatarajoState};
+use smithay::backend::renderer::element::surface::render_elements_from_surface_tree;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::{ImportAll, Renderer};
+use smithay::desktop::utils::under_from_surface_tree;
+use smithay::desktop::{Space, WindowSurfaceType};
+use smithay::input::pointer::MotionEvent;
+use smithay::output::Output;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::DisplayHandle;
+use smithay::utils::{Logical, Point, Serial, SERIAL_COUNTER};
+use smithay::wayland::session_lock::{LockSurface, SessionLockManagerState, SessionLocker};
+use std::time::Duration;
+
+/// Used while no lock surface is shown, e.g. the locker crashed.
+pub(crate) const LOCKED_CLEAR_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 1.0];
+
+pub(crate) struct SessionLockState {
+    pub manager_state: SessionLockManagerState,
+    /// `Some` while the session is locked.
+    lock: Option<SessionLock>,
+}
+
+#[derive(Default)]
+struct SessionLock {
+    surfaces: Vec<(Output, LockSurface)>,
+    /// `Some` until every output shows a locked frame.
+    locker: Option<SessionLocker>,
+    /// Outputs that have shown a locked frame since `locker` is set.
+    locked_outputs: Vec<Output>,
+}
+
+impl SessionLockState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        let manager_state =
+            SessionLockManagerState::new::<TatarajoState, _>(display_handle, |client| {
+                client
+                    .get_data::<ClientState>()
+                    .map_or(true, |client_state| client_state.security_context.is_none())
+            });
+        Self {
+            manager_state,
+            lock: None,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// Returns the alive lock surface for the output.
+    pub fn lock_surface(&self, output: &Output) -> Option<&LockSurface> {
+        self.lock
+            .as_ref()?
+            .surfaces
+            .iter()
+            .find_map(|(o, surface)| (o == output && surface.alive()).then_some(surface))
+    }
+
+    /// Called by backends when `output` has submitted a frame built while locked, or shows nothing as it is off.
+    pub(crate) fn on_locked_frame<E>(&mut self, output: &Output, space: &Space<E>)
+    where
+        E: smithay::desktop::space::SpaceElement + PartialEq,
+    {
+        let Some(lock) = &mut self.lock else {
+            return;
+        };
+        if lock.locker.is_some() && !lock.locked_outputs.contains(output) {
+            lock.locked_outputs.push(output.clone());
+        }
+        self.confirm_lock(space);
+    }
+
+    /// Sends `locked` if every output has shown a locked frame.
+    fn confirm_lock<E>(&mut self, space: &Space<E>)
+    where
+        E: smithay::desktop::space::SpaceElement + PartialEq,
+    {
+        let Some(lock) = &mut self.lock else {
+            return;
+        };
+        if space
+            .outputs()
+            .all(|output| lock.locked_outputs.contains(output))
+        {
+            if let Some(locker) = lock.locker.take() {
+                locker.lock();
+            }
+            lock.locked_outputs.clear();
+        }
+    }
+
+    /// Returns elements of the lock surface for the output. Empty if there is no lock surface.
+    pub(crate) fn render_elements<R>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<CustomRenderElement<R>>
+    where
+        R: Renderer + ImportAll,
+        R::TextureId: Clone + 'static,
+    {
+        let Some(surface) = self.lock_surface(output) else {
+            return vec![];
+        };
+        let scale = output.current_scale().fractional_scale();
+        render_elements_from_surface_tree(
+            renderer,
+            surface.wl_surface(),
+            (0, 0),
+            scale,
+            1.0,
+            Kind::Unspecified,
+        )
+    }
+
+    /// Returns the lock surface under `pos`, which is in the global coordinate.
+    pub(crate) fn surface_under<E>(
+        &self,
+        space: &Space<E>,
+        pos: Point<f64, Logical>,
+    ) -> Option<(PointerFocusTarget, Point<i32, Logical>)>
+    where
+        E: smithay::desktop::space::SpaceElement + PartialEq,
+    {
+        let output = space.outputs().find(|output| {
+            space
+                .output_geometry(output)
+                .unwrap()
+                .to_f64()
+                .contains(pos)
+        })?;
+        let output_loc = space.output_geometry(output).unwrap().loc;
+        let surface = self.lock_surface(output)?;
+        under_from_surface_tree(
+            surface.wl_surface(),
+            pos - output_loc.to_f64(),
+            (0, 0),
+            WindowSurfaceType::ALL,
+        )
+        .map(|(surface, loc)| (PointerFocusTarget::from(surface), loc + output_loc))
+    }
+}
+
+impl TatarajoState {
+    pub(crate) fn lock_session(&mut self, locker: SessionLocker) {
+        let session_lock_state = &mut self.inner.session_lock_state;
+        let has_alive_surface = session_lock_state
+            .lock
+            .as_ref()
+            .is_some_and(|lock| lock.surfaces.iter().any(|(_, surface)| surface.alive()));
+        if has_alive_surface {
+            // Another locker is running. Dropping `locker` without locking sends `finished`.
+            return;
+        }
+
+        // Keep the surfaces if any, so that a new locker replaces a crashed one.
+        let lock = session_lock_state
+            .lock
+            .get_or_insert_with(SessionLock::default);
+        // Nothing other than lock surfaces is rendered from the next frame. `locked` is sent when it is shown.
+        lock.locker = Some(locker);
+        lock.locked_outputs.clear();
+        // There may be no output to wait for.
+        session_lock_state.confirm_lock(&self.inner.space);
+
+        self.clear_keyseq();
+        self.finish_key_capture();
+        self.focus_lock_surface(None);
+        self.inner.refresh_idle_inhibit();
+    }
+
+    pub(crate) fn unlock_session(&mut self) {
+        self.inner.session_lock_state.lock = None;
+        self.reflect_focus_from_stackset(None);
+        self.refresh_pointer_focus();
+    }
+
+    pub(crate) fn on_new_lock_surface(&mut self, surface: LockSurface, wl_output: WlOutput) {
+        let Some(output) = Output::from_resource(&wl_output) else {
+            return;
+        };
+        let Some(geometry) = self.inner.space.output_geometry(&output) else {
+            return;
+        };
+        let Some(lock) = &mut self.inner.session_lock_state.lock else {
+            return;
+        };
+
+        surface.with_pending_state(|state| {
+            state.size = Some((geometry.size.w as u32, geometry.size.h as u32).into());
+        });
+        surface.send_configure();
+        lock.surfaces.retain(|(o, _)| *o != output);
+        lock.surfaces.push((output, surface));
+
+        self.focus_lock_surface(None);
+        self.refresh_pointer_focus();
+    }
+
+    /// Gives keyboard focus to the lock surface on the output the pointer is on, or any alive one.
+    pub(crate) fn focus_lock_surface(&mut self, serial: Option<Serial>) {
+        let Some(lock) = &self.inner.session_lock_state.lock else {
+            return;
+        };
+        let pointer = self.inner.seat.get_pointer().unwrap();
+        let pos = pointer.current_location();
+        let output = self.inner.space.outputs().find(|output| {
+            self.inner
+                .space
+                .output_geometry(output)
+                .unwrap()
+                .to_f64()
+                .contains(pos)
+        });
+        let surface = output
+            .and_then(|output| self.inner.session_lock_state.lock_surface(output))
+            .or_else(|| {
+                lock.surfaces
+                    .iter()
+                    .find_map(|(_, surface)| surface.alive().then_some(surface))
+            });
+        let focus =
+            surface.map(|surface| KeyboardFocusTarget::LockSurface(surface.wl_surface().clone()));
+
+        let keyboard = self.inner.seat.get_keyboard().unwrap();
+        if keyboard.current_focus() != focus {
+            let serial = serial.unwrap_or_else(|| SERIAL_COUNTER.next_serial());
+            keyboard.set_focus(self, focus, serial);
+        }
+    }
+
+    /// Updates pointer focus, as surfaces under the pointer are replaced.
+    fn refresh_pointer_focus(&mut self) {
+        let pointer = self.inner.seat.get_pointer().unwrap();
+        let location = pointer.current_location();
+        let under = self.surface_under(location);
+        let time = Duration::from(self.inner.clock.now()).as_millis() as u32;
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                serial: SERIAL_COUNTER.next_serial(),
+                time,
+                location,
+            },
+        );
+        pointer.frame(self);
+    }
+}
@@ -1,5 +1,7 @@
 use crate::backend::BackendI;
 use crate::state::TatarajoState;
+use crate::util::Id;
+use crate::view::window::{Window, WindowMetadataChange};
 use crate::ClientState;
 use smithay::backend::renderer::utils::on_commit_buffer_handler;
 use smithay::desktop::{layer_map_for_output, LayerSurface};
@@ -131,6 +133,53 @@ impl WlrLayerShellHandler for TatarajoState {
 }
 
 impl TatarajoState {
+    /// Registers a new window and focuses it if it is on the focused workspace.
+    ///
+    /// A window of a process spawned by `Action::Spawn` is placed on the workspace it was spawned for.
+    pub(crate) fn map_new_window(&mut self, window: smithay::desktop::Window, pid: Option<u32>) {
+        let workspace = pid.and_then(|pid| self.spawned_workspace_for_pid(pid));
+        let window = Window::new(window, pid);
+        let window_id = self.inner.view.register_window(
+            window,
+            self.inner.config.insert_policy,
+            workspace.as_ref(),
+        );
+        self.inner.view.layout(&mut self.inner.space);
+        let ws = self.inner.view.stackset().workspaces.focus();
+        if ws.stack.as_vec().contains(&window_id) {
+            self.inner.view.set_focus(window_id);
+        }
+        self.reflect_focus_from_stackset(None);
+    }
+
+    /// Called when metadata of a window changes. Consumers of window metadata, e.g. the status log and
+    /// Wayland protocols, are notified from here.
+    pub(crate) fn on_window_metadata_changed(
+        &mut self,
+        window_id: Id<Window>,
+        change: WindowMetadataChange,
+    ) {
+        debug!("window metadata changed: {:?}, {:?}", window_id, change);
+
+        self.update_status_log();
+        self.inner.refresh_ext_workspace();
+        self.inner.refresh_foreign_toplevel();
+    }
+
+    /// Returns the window of `surface` among all workspaces.
+    pub(crate) fn view_window_for_surface(&self, surface: &WlSurface) -> Option<Id<Window>> {
+        self.inner
+            .view
+            .windows()
+            .find(|window| {
+                window
+                    .smithay_window()
+                    .wl_surface()
+                    .is_some_and(|s| s == *surface)
+            })
+            .map(|window| window.id())
+    }
+
     pub fn window_for_surface(&self, surface: &WlSurface) -> Option<crate::view::window::Window> {
         self.inner
             .space
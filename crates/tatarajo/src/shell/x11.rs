@@ -1,5 +1,6 @@
 use crate::focus::KeyboardFocusTarget;
 use crate::state::TatarajoState;
+use crate::view::window::WindowMetadataChange;
 use smithay::utils::{Logical, Rectangle};
 use smithay::wayland::selection::data_device::{
     clear_data_device_selection, current_data_device_selection_userdata,
@@ -10,7 +11,7 @@ use smithay::wayland::selection::primary_selection::{
     set_primary_selection,
 };
 use smithay::wayland::selection::SelectionTarget;
-use smithay::xwayland::xwm::{Reorder, ResizeEdge as X11ResizeEdge, XwmId};
+use smithay::xwayland::xwm::{Reorder, ResizeEdge as X11ResizeEdge, WmWindowProperty, XwmId};
 use smithay::xwayland::{X11Surface, X11Wm, XwmHandler};
 use std::os::unix::io::OwnedFd;
 
@@ -25,19 +26,14 @@ impl XwmHandler for TatarajoState {
     fn map_window_request(&mut self, _xwm: XwmId, window: X11Surface) {
         window.set_mapped(true).unwrap();
 
+        let pid = window.pid();
         let window = smithay::desktop::Window::new_x11_window(window);
-        let window_id = self.inner.view.register_window(window);
-        self.inner.view.layout(&mut self.inner.space);
-        self.inner.view.set_focus(window_id);
-        self.reflect_focus_from_stackset(None);
+        self.map_new_window(window, pid);
     }
 
     fn mapped_override_redirect_window(&mut self, _xwm: XwmId, window: X11Surface) {
         let window = smithay::desktop::Window::new_x11_window(window);
-        let window_id = self.inner.view.register_window(window);
-        self.inner.view.layout(&mut self.inner.space);
-        self.inner.view.set_focus(window_id);
-        self.reflect_focus_from_stackset(None);
+        self.map_new_window(window, None);
     }
 
     fn unmapped_window(&mut self, _xwm: XwmId, window: X11Surface) {
@@ -57,6 +53,25 @@ impl XwmHandler for TatarajoState {
 
     fn destroyed_window(&mut self, _xwm: XwmId, _window: X11Surface) {}
 
+    fn property_notify(&mut self, _xwm: XwmId, window: X11Surface, property: WmWindowProperty) {
+        let change = match property {
+            WmWindowProperty::Title => WindowMetadataChange::Title,
+            WmWindowProperty::Class => WindowMetadataChange::AppId,
+            WmWindowProperty::Hints => WindowMetadataChange::Urgency,
+            _ => return,
+        };
+        let Some(window_id) = self
+            .inner
+            .view
+            .windows()
+            .find(|w| matches!(w.smithay_window().x11_surface(), Some(w) if w == &window))
+            .map(|w| w.id())
+        else {
+            return;
+        };
+        self.on_window_metadata_changed(window_id, change);
+    }
+
     fn configure_request(
         &mut self,
         _xwm: XwmId,
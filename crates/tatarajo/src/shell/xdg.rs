@@ -1,7 +1,9 @@
 use crate::state::TatarajoState;
+use crate::view::window::WindowMetadataChange;
 use smithay::desktop::{find_popup_root_surface, get_popup_toplevel_coords, PopupKind};
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_server::protocol::wl_seat;
+use smithay::reexports::wayland_server::Resource;
 use smithay::utils::Serial;
 use smithay::wayland::shell::xdg::{
     PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState,
@@ -13,11 +15,25 @@ impl XdgShellHandler for TatarajoState {
     }
 
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
+        let pid = surface
+            .wl_surface()
+            .client()
+            .and_then(|client| client.get_credentials(&self.inner.display_handle).ok())
+            .map(|credentials| credentials.pid as u32);
         let window = smithay::desktop::Window::new_wayland_window(surface);
-        let window_id = self.inner.view.register_window(window);
-        self.inner.view.layout(&mut self.inner.space);
-        self.inner.view.set_focus(window_id);
-        self.reflect_focus_from_stackset(None);
+        self.map_new_window(window, pid);
+    }
+
+    fn title_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(window_id) = self.view_window_for_surface(surface.wl_surface()) {
+            self.on_window_metadata_changed(window_id, WindowMetadataChange::Title);
+        }
+    }
+
+    fn app_id_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(window_id) = self.view_window_for_surface(surface.wl_surface()) {
+            self.on_window_metadata_changed(window_id, WindowMetadataChange::AppId);
+        }
     }
 
     fn new_popup(&mut self, surface: PopupSurface, _positioner: PositionerState) {
@@ -1,9 +1,22 @@
 use crate::action::Action;
 use crate::backend::{Backend, BackendI};
+use crate::config::Config;
 use crate::cursor::Cursor;
-use crate::envvar::EnvVar;
+use crate::envvar::{EnvVar, LibinputDeviceConfig, XkbConfig, XkbKeymapConfig};
+use crate::ext_workspace::ExtWorkspaceState;
+use crate::foreign_toplevel::ForeignToplevelState;
+use crate::idle::IdleState;
+use crate::input::capture::KeyCapture;
+use crate::input::gesture::SwipeTracker;
 use crate::input::{KeySeq, Keymap};
 use crate::input_event::FocusUpdateDecider;
+use crate::output_power::OutputPowerState;
+use crate::overlay::{Overlay, OverlayAnchor};
+use crate::process::ProcessManager;
+use crate::screencopy::ScreencopyState;
+use crate::session_lock::SessionLockState;
+use crate::status_log::StatusLogger;
+use crate::tablet::TabletToolCursors;
 use crate::util::EventHandler;
 use crate::view::stackset::WorkspaceTag;
 use crate::view::view::View;
@@ -14,13 +27,15 @@ use smithay::backend::renderer::element::{
     default_primary_scanout_output_compare, RenderElementStates,
 };
 use smithay::desktop::utils::{
-    surface_presentation_feedback_flags_from_states, surface_primary_scanout_output,
-    update_surface_primary_scanout_output, OutputPresentationFeedback,
+    send_frames_surface_tree, surface_presentation_feedback_flags_from_states,
+    surface_primary_scanout_output, update_surface_primary_scanout_output,
+    with_surfaces_surface_tree, OutputPresentationFeedback,
 };
 use smithay::desktop::{PopupManager, Space};
+use smithay::input::keyboard::Layout;
 use smithay::input::pointer::{CursorImageStatus, PointerHandle};
 use smithay::input::{Seat, SeatState};
-use smithay::reexports::calloop::{EventLoop, LoopHandle, LoopSignal};
+use smithay::reexports::calloop::{EventLoop, LoopHandle, LoopSignal, RegistrationToken};
 use smithay::reexports::wayland_server::backend::{ClientData, ClientId, DisconnectReason};
 use smithay::reexports::wayland_server::{Display, DisplayHandle};
 use smithay::utils::{Clock, Monotonic, Point, Rectangle, Size};
@@ -47,6 +62,7 @@ use smithay::wayland::xdg_activation::XdgActivationState;
 use smithay::wayland::xdg_foreign::XdgForeignState;
 use smithay::wayland::xwayland_keyboard_grab::XWaylandKeyboardGrabState;
 use smithay::xwayland::{X11Wm, XWayland, XWaylandEvent};
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -90,11 +106,18 @@ pub(crate) struct InnerState {
     pub xdg_activation_state: XdgActivationState,
     pub xdg_shell_state: XdgShellState,
     pub xdg_foreign_state: XdgForeignState,
+    pub ext_workspace_state: ExtWorkspaceState,
+    pub foreign_toplevel_state: ForeignToplevelState,
+    pub screencopy_state: ScreencopyState,
+    pub session_lock_state: SessionLockState,
+    pub idle_state: IdleState,
+    pub output_power_state: OutputPowerState,
 
     pub dnd_icon: Option<wayland_server::protocol::wl_surface::WlSurface>,
 
     // input-related fields
     pub cursor_status: Arc<Mutex<CursorImageStatus>>,
+    pub tablet_tool_cursors: TabletToolCursors,
     pub seat_name: String,
     pub seat: Seat<TatarajoState>,
     pub clock: Clock<Monotonic>,
@@ -107,8 +130,30 @@ pub(crate) struct InnerState {
     pub xdisplay: Option<u32>,
 
     pub envvar: EnvVar,
+    pub xkb_config: XkbConfig,
+    /// Keymap config currently set to the keyboard. It is switched per device that sends key events.
+    pub xkb_keymap_config: XkbKeymapConfig,
+    /// Active layout per keymap config, restored when the keymap config is switched back.
+    pub xkb_layouts: HashMap<XkbKeymapConfig, Layout>,
+    pub config: Config,
     pub keymap: Keymap<Action>,
     pub keyseq: KeySeq,
+    /// `Some` iff a timer to discard a pending key sequence is registered.
+    pub keyseq_timer: Option<RegistrationToken>,
+    pub keyseq_overlay: Overlay,
+    pub notification_timer: Option<RegistrationToken>,
+    pub notification_overlay: Overlay,
+    /// `Some` iff keys are captured in place of the keymap, e.g. by the window picker.
+    pub key_capture: Option<Box<dyn KeyCapture>>,
+    pub key_capture_overlay: Overlay,
+    /// Submitted texts of prompts per `Prompt::with_history()` key, most recent first.
+    pub prompt_history: HashMap<String, Vec<String>>,
+    /// Resolved configs of input devices, keyed by device id.
+    pub input_device_configs: HashMap<String, LibinputDeviceConfig>,
+    /// `Some` iff a swipe gesture bound in `Config::swipe_bindings` is in progress.
+    pub swipe_tracker: Option<SwipeTracker>,
+    pub process_manager: ProcessManager,
+    pub status_logger: Option<StatusLogger>,
     pub view: View,
     pub focus_update_decider: FocusUpdateDecider,
 }
@@ -122,7 +167,11 @@ where
 }
 
 impl TatarajoState {
-    pub fn run(workspace_tags: Vec<WorkspaceTag>, keymap: Keymap<Action>) -> eyre::Result<()> {
+    pub fn run(
+        workspace_tags: Vec<WorkspaceTag>,
+        keymap: Keymap<Action>,
+        config: Config,
+    ) -> eyre::Result<()> {
         use crate::backend::udev::UdevBackend;
         #[cfg(feature = "winit")]
         use crate::backend::winit::WinitBackend;
@@ -148,6 +197,7 @@ impl TatarajoState {
 
         let mut this = Self::new(
             envvar,
+            config,
             workspace_tags,
             keymap,
             event_loop.handle(),
@@ -164,6 +214,7 @@ impl TatarajoState {
 
     fn new(
         envvar: EnvVar,
+        config: Config,
         workspace_tags: Vec<WorkspaceTag>,
         keymap: Keymap<Action>,
         loop_handle: LoopHandle<'static, TatarajoState>,
@@ -193,6 +244,21 @@ impl TatarajoState {
                 .map_err(|e| eyre::eyre!("{}", e))?;
         }
 
+        // Reap children spawned by `Action::Spawn`.
+        {
+            use smithay::reexports::calloop::signals::{Signal, Signals};
+
+            let signals = Signals::new(&[Signal::SIGCHLD]).wrap_err("listen SIGCHLD")?;
+            loop_handle
+                .insert_source(signals, |_, _, state| {
+                    state.reap_children();
+                    state.reap_status_log_program();
+                })
+                .map_err(|e| eyre::eyre!("{}", e))?;
+        }
+        let process_manager = ProcessManager::new(config.spawn_log_dir.clone());
+        let status_logger = config.status_log.clone().map(StatusLogger::new);
+
         // Initialize `WAYLAND_DISPLAY` socket to listen Wayland clients.
         let socket_source = ListeningSocketSource::new_auto()?;
         let socket_name = socket_source.socket_name().to_string_lossy().into_owned();
@@ -228,6 +294,12 @@ impl TatarajoState {
         let xdg_activation_state = XdgActivationState::new::<Self>(&display_handle);
         let xdg_shell_state = XdgShellState::new::<Self>(&display_handle);
         let xdg_foreign_state = XdgForeignState::new::<Self>(&display_handle);
+        let ext_workspace_state = ExtWorkspaceState::new(&display_handle);
+        let foreign_toplevel_state = ForeignToplevelState::new(&display_handle);
+        let screencopy_state = ScreencopyState::new(&display_handle);
+        let session_lock_state = SessionLockState::new(&display_handle);
+        let idle_state = IdleState::new(&display_handle, loop_handle.clone());
+        let output_power_state = OutputPowerState::new(&display_handle);
         TextInputManagerState::new::<Self>(&display_handle);
         InputMethodManagerState::new::<Self, _>(&display_handle, |_client| true);
         VirtualKeyboardManagerState::new::<Self, _>(&display_handle, |_client| true);
@@ -251,27 +323,27 @@ impl TatarajoState {
 
         let cursor_status = Arc::new(Mutex::new(CursorImageStatus::default_named()));
         let pointer = seat.add_pointer();
+        seat.add_touch();
 
-        // TODO: Support proper config.
-        if let Some(config) = &envvar.xkb_config()? {
-            let xkb_config = smithay::input::keyboard::XkbConfig {
-                layout: &config.layout,
-                ..Default::default()
-            };
-            seat.add_keyboard(
-                xkb_config,
-                config.repeat_delay.into(),
-                config.repeat_rate.into(),
-            )
-            .unwrap();
-        }
+        let xkb_config = envvar.xkb_config()?;
+        seat.add_keyboard(
+            xkb_config.keymap.as_smithay(),
+            xkb_config.repeat_delay.into(),
+            xkb_config.repeat_rate.into(),
+        )
+        .wrap_err("add keyboard with TATARAJO_XKB_CONFIG")?;
+
+        let focus_update_decider =
+            FocusUpdateDecider::new(config.focus_policy, config.focus_distance_threshold);
 
-        let cursor_status2 = cursor_status.clone();
-        seat.tablet_seat()
-            .on_cursor_surface(move |_tool, new_status| {
-                // TODO: tablet tools should have their own cursors
-                *cursor_status2.lock().unwrap() = new_status;
-            });
+        let tablet_tool_cursors = TabletToolCursors::default();
+        {
+            let tablet_tool_cursors = tablet_tool_cursors.clone();
+            seat.tablet_seat()
+                .on_cursor_surface(move |tool, new_status| {
+                    tablet_tool_cursors.set_status(tool, new_status);
+                });
+        }
 
         let keyboard_shortcuts_inhibit_state =
             KeyboardShortcutsInhibitState::new::<Self>(&display_handle);
@@ -320,8 +392,15 @@ impl TatarajoState {
                 xdg_activation_state,
                 xdg_shell_state,
                 xdg_foreign_state,
+                ext_workspace_state,
+                foreign_toplevel_state,
+                screencopy_state,
+                session_lock_state,
+                idle_state,
+                output_power_state,
                 dnd_icon: None,
                 cursor_status,
+                tablet_tool_cursors,
                 seat_name,
                 seat,
                 pointer,
@@ -331,10 +410,25 @@ impl TatarajoState {
                 xdisplay: None,
 
                 envvar,
+                xkb_keymap_config: xkb_config.keymap.clone(),
+                xkb_layouts: HashMap::new(),
+                xkb_config,
+                config,
                 keymap,
                 keyseq: KeySeq::new(),
+                keyseq_timer: None,
+                keyseq_overlay: Overlay::default(),
+                notification_timer: None,
+                notification_overlay: Overlay::new(OverlayAnchor::Top),
+                key_capture: None,
+                key_capture_overlay: Overlay::new(OverlayAnchor::Center),
+                prompt_history: HashMap::new(),
+                input_device_configs: HashMap::new(),
+                swipe_tracker: None,
+                process_manager,
+                status_logger,
                 view,
-                focus_update_decider: FocusUpdateDecider::new(),
+                focus_update_decider,
             },
         })
     }
@@ -365,10 +459,39 @@ pub(crate) fn post_repaint(
     output: &smithay::output::Output,
     render_element_states: &RenderElementStates,
     space: &Space<crate::view::window::Window>,
+    session_lock: &SessionLockState,
     dmabuf_feedback: Option<SurfaceDmabufFeedback<'_>>,
     time: Duration,
 ) {
     let throttle = Some(Duration::from_secs(1));
+    // Only the lock surface is shown while locked, so hidden windows and layers get no frame callbacks.
+    let is_locked = session_lock.is_locked();
+
+    if let Some(lock_surface) = session_lock.lock_surface(output) {
+        with_surfaces_surface_tree(lock_surface.wl_surface(), |surface, states| {
+            let primary_scanout_output = update_surface_primary_scanout_output(
+                surface,
+                output,
+                states,
+                render_element_states,
+                default_primary_scanout_output_compare,
+            );
+
+            if let Some(output) = primary_scanout_output {
+                with_fractional_scale(states, |fraction_scale| {
+                    fraction_scale.set_preferred_scale(output.current_scale().fractional_scale());
+                });
+            }
+        });
+
+        send_frames_surface_tree(
+            lock_surface.wl_surface(),
+            output,
+            time,
+            throttle,
+            surface_primary_scanout_output,
+        );
+    }
 
     for window in space.elements() {
         window.smithay_window().with_surfaces(|surface, states| {
@@ -387,7 +510,7 @@ pub(crate) fn post_repaint(
             }
         });
 
-        if space.outputs_for_element(window).contains(output) {
+        if !is_locked && space.outputs_for_element(window).contains(output) {
             window.smithay_window().send_frame(
                 output,
                 time,
@@ -429,6 +552,9 @@ pub(crate) fn post_repaint(
             }
         });
 
+        if is_locked {
+            continue;
+        }
         layer_surface.send_frame(output, time, throttle, surface_primary_scanout_output);
         if let Some(dmabuf_feedback) = dmabuf_feedback {
             layer_surface.send_dmabuf_feedback(
@@ -1,6 +1,7 @@
 use crate::focus::{KeyboardFocusTarget, PointerFocusTarget};
 use crate::backend::{DmabufHandlerDelegate, BackendI};
 use crate::state::{ClientState, TatarajoState};
+use crate::view::window::WindowMetadataChange;
 use smithay::desktop::space::SpaceElement;
 use smithay::desktop::utils::surface_primary_scanout_output;
 use smithay::desktop::{PopupKind, PopupManager};
@@ -10,12 +11,16 @@ use smithay::input::{Seat, SeatHandler, SeatState};
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::
     zxdg_toplevel_decoration_v1::Mode as DecorationMode;
 use smithay::reexports::wayland_protocols::xdg::decoration::{self as xdg_decoration};
+use smithay::output::Output;
 use smithay::reexports::wayland_server::protocol::wl_data_source::WlDataSource;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::Resource;
 use smithay::utils::Rectangle;
 use smithay::wayland::compositor::{get_parent, with_states};
 use smithay::wayland::fractional_scale::{with_fractional_scale, FractionalScaleHandler};
+use smithay::wayland::idle_inhibit::IdleInhibitHandler;
+use smithay::wayland::idle_notify::{IdleNotifierHandler, IdleNotifierState};
 use smithay::wayland::input_method::{InputMethodHandler, PopupSurface};
 use smithay::wayland::keyboard_shortcuts_inhibit::{
     KeyboardShortcutsInhibitHandler, KeyboardShortcutsInhibitState, KeyboardShortcutsInhibitor,
@@ -35,6 +40,9 @@ use smithay::wayland::selection::primary_selection::{
 };
 use smithay::wayland::selection::wlr_data_control::{DataControlHandler, DataControlState};
 use smithay::wayland::selection::{SelectionHandler, SelectionSource, SelectionTarget};
+use smithay::wayland::session_lock::{
+    LockSurface, SessionLockHandler, SessionLockManagerState, SessionLocker,
+};
 use smithay::wayland::shell::xdg::decoration::XdgDecorationHandler;
 use smithay::wayland::shell::xdg::{ToplevelSurface, XdgToplevelSurfaceData};
 use smithay::wayland::shm::{ShmHandler, ShmState};
@@ -76,7 +84,14 @@ impl ServerDndGrabHandler for TatarajoState {
 
 smithay::delegate_data_device!(TatarajoState);
 
-impl OutputHandler for TatarajoState {}
+impl OutputHandler for TatarajoState {
+    fn output_bound(&mut self, output: Output, wl_output: WlOutput) {
+        // Sends `output_enter` of ext-workspace and foreign toplevels for the new `wl_output`.
+        self.inner.refresh_ext_workspace();
+        self.inner
+            .on_foreign_toplevel_output_bound(&output, &wl_output);
+    }
+}
 
 smithay::delegate_output!(TatarajoState);
 
@@ -250,10 +265,33 @@ impl XdgActivationHandler for TatarajoState {
 
     fn request_activation(
         &mut self,
-        _token: XdgActivationToken,
+        token: XdgActivationToken,
         token_data: XdgActivationTokenData,
         surface: WlSurface,
     ) {
+        if self
+            .inner
+            .process_manager
+            .find_by_activation_token(&token)
+            .is_some()
+        {
+            // A window of a program spawned by `Action::Spawn`. Place it on the workspace it was spawned for instead
+            // of raising it on the focused one, and activate it there. It happens e.g. if the window is created by an
+            // existing instance.
+            let tag = self.spawned_workspace_for_activation_token(&token);
+            if let (Some(tag), Some(window_id)) = (tag, self.view_window_for_surface(&surface)) {
+                if let Err(err) = self.inner.view.move_window_to_workspace(window_id, &tag) {
+                    warn!("{}", err);
+                }
+                if self.inner.view.stackset().workspaces.focus().tag == tag {
+                    self.inner.view.set_focus(window_id);
+                }
+                self.inner.view.layout(&mut self.inner.space);
+                self.reflect_focus_from_stackset(None);
+            }
+            return;
+        }
+
         if token_data.timestamp.elapsed().as_secs() < 10 {
             // Just grant the wish
             let w = self
@@ -270,8 +308,21 @@ impl XdgActivationHandler for TatarajoState {
                 .cloned();
             if let Some(window) = w {
                 self.inner.space.raise_element(&window, true);
+                return;
             }
         }
+
+        // The window is on another workspace, or the request is too old. Ask for attention instead.
+        let Some(window_id) = self.view_window_for_surface(&surface) else {
+            return;
+        };
+        if self.inner.view.focused_window().map(|w| w.id()) == Some(window_id) {
+            return;
+        }
+        if let Some(window) = self.inner.view.window(window_id) {
+            window.set_urgent(true);
+        }
+        self.on_window_metadata_changed(window_id, WindowMetadataChange::Urgency);
     }
 }
 
@@ -418,6 +469,46 @@ impl SecurityContextHandler for TatarajoState {
 
 smithay::delegate_security_context!(TatarajoState);
 
+impl SessionLockHandler for TatarajoState {
+    fn lock_state(&mut self) -> &mut SessionLockManagerState {
+        &mut self.inner.session_lock_state.manager_state
+    }
+
+    fn lock(&mut self, confirmation: SessionLocker) {
+        self.lock_session(confirmation);
+    }
+
+    fn unlock(&mut self) {
+        self.unlock_session();
+    }
+
+    fn new_surface(&mut self, surface: LockSurface, output: WlOutput) {
+        self.on_new_lock_surface(surface, output);
+    }
+}
+
+smithay::delegate_session_lock!(TatarajoState);
+
+impl IdleNotifierHandler for TatarajoState {
+    fn idle_notifier_state(&mut self) -> &mut IdleNotifierState<Self> {
+        &mut self.inner.idle_state.notifier_state
+    }
+}
+
+smithay::delegate_idle_notify!(TatarajoState);
+
+impl IdleInhibitHandler for TatarajoState {
+    fn inhibit(&mut self, surface: WlSurface) {
+        self.inner.add_idle_inhibitor(surface);
+    }
+
+    fn uninhibit(&mut self, surface: WlSurface) {
+        self.inner.remove_idle_inhibitor(&surface);
+    }
+}
+
+smithay::delegate_idle_inhibit!(TatarajoState);
+
 impl XWaylandKeyboardGrabHandler for TatarajoState {
     fn keyboard_focus_for_xsurface(&self, surface: &WlSurface) -> Option<KeyboardFocusTarget> {
         let window = self
@@ -0,0 +1,349 @@
+use crate::state::TatarajoState;
+use crate::view::stackset::WorkspaceTag;
+use smithay::reexports::rustix;
+use smithay::reexports::rustix::fs::OFlags;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const RESPAWN_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RESPAWN_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// State of tatarajo given to `StatusLog`'s formatter, like xmonad's `dynamicLog`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub workspaces: Vec<WorkspaceStatus>,
+    pub layout: String,
+    /// Title of the focused window.
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceStatus {
+    pub tag: WorkspaceTag,
+    pub state: WorkspaceState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceState {
+    Current,
+    /// Not current and has an urgent window.
+    Urgent,
+    NonEmpty,
+    Empty,
+}
+
+impl Status {
+    /// Formats like `[1] 2 3* : LayoutFull : title`. Empty workspaces are omitted.
+    pub fn default_format(&self) -> String {
+        let workspaces = self
+            .workspaces
+            .iter()
+            .filter_map(|ws| match ws.state {
+                WorkspaceState::Current => Some(format!("[{}]", ws.tag)),
+                WorkspaceState::Urgent => Some(format!("{}*", ws.tag)),
+                WorkspaceState::NonEmpty => Some(ws.tag.to_string()),
+                WorkspaceState::Empty => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut s = format!("{} : {}", workspaces, self.layout);
+        if let Some(title) = &self.title {
+            s.push_str(" : ");
+            s.push_str(title);
+        }
+        s
+    }
+}
+
+/// Where a status line is written to.
+#[derive(Debug, Clone)]
+pub enum StatusLogOutput {
+    Stdout,
+    /// A named pipe, e.g. read by xmobar's `PipeReader`. It is (re)opened when a reader exists.
+    Fifo(PathBuf),
+    /// Stdin of a program spawned by `/bin/sh -c`, e.g. `lemonbar`.
+    Command(String),
+}
+
+/// Writes a line of `Status` on every change of it.
+#[derive(Clone)]
+pub struct StatusLog {
+    pub output: StatusLogOutput,
+    pub formatter: Arc<dyn Fn(&Status) -> String>,
+}
+
+impl std::fmt::Debug for StatusLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatusLog")
+            .field("output", &self.output)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StatusLog {
+    /// Uses `Status::default_format()`.
+    pub fn new(output: StatusLogOutput) -> Self {
+        Self {
+            output,
+            formatter: Arc::new(Status::default_format),
+        }
+    }
+
+    pub fn with_formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(&Status) -> String + 'static,
+    {
+        self.formatter = Arc::new(formatter);
+        self
+    }
+}
+
+pub(crate) struct StatusLogger {
+    log: StatusLog,
+    writer: Option<Box<dyn Write>>,
+    /// Program of `StatusLogOutput::Command`.
+    child: Option<Child>,
+    spawned_at: Instant,
+    /// The program is not respawned until then after it exited.
+    respawn_at: Option<Instant>,
+    /// Doubled while the program exits soon after spawned, e.g. it fails at startup.
+    respawn_backoff: Duration,
+    last_line: Option<String>,
+}
+
+impl StatusLogger {
+    pub fn new(log: StatusLog) -> Self {
+        Self {
+            log,
+            writer: None,
+            child: None,
+            spawned_at: Instant::now(),
+            respawn_at: None,
+            respawn_backoff: Duration::ZERO,
+            last_line: None,
+        }
+    }
+
+    /// Reaps the program of `StatusLogOutput::Command` if it exited, so that it is respawned. Returns when it can be
+    /// respawned.
+    ///
+    /// Only called on `SIGCHLD`, so that a respawn is scheduled exactly once per exit.
+    pub fn reap_child(&mut self) -> Option<Instant> {
+        let child = self.child.as_mut()?;
+        match child.try_wait() {
+            Ok(None) => return None,
+            Ok(Some(status)) => warn!("status log program exited: status = {}", status),
+            Err(err) => warn!("failed to wait status log program: error = {}", err),
+        }
+
+        self.respawn_backoff = if self.spawned_at.elapsed() < RESPAWN_BACKOFF_MAX {
+            (self.respawn_backoff * 2).clamp(RESPAWN_BACKOFF_MIN, RESPAWN_BACKOFF_MAX)
+        } else {
+            RESPAWN_BACKOFF_MIN
+        };
+        let respawn_at = Instant::now() + self.respawn_backoff;
+        self.respawn_at = Some(respawn_at);
+        self.child = None;
+        self.writer = None;
+        self.last_line = None;
+        Some(respawn_at)
+    }
+
+    fn log(&mut self, status: &Status) {
+        let line = (self.log.formatter)(status);
+        if self.last_line.as_ref() == Some(&line) {
+            return;
+        }
+
+        if self.writer.is_none() {
+            match self.open() {
+                Ok(writer) => self.writer = writer,
+                Err(err) => {
+                    warn!("failed to open status log output: {}", err);
+                    return;
+                }
+            }
+        }
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+
+        match writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+            Ok(()) => self.last_line = Some(line),
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                // The reader is slow. Skip this line.
+            }
+            Err(err) => {
+                warn!("failed to write status log: {}", err);
+                self.writer = None;
+                self.last_line = None;
+            }
+        }
+    }
+
+    /// Returns `None` if the output is not ready, i.e. a FIFO without readers or an exited program.
+    fn open(&mut self) -> std::io::Result<Option<Box<dyn Write>>> {
+        match &self.log.output {
+            StatusLogOutput::Stdout => Ok(Some(Box::new(std::io::stdout()))),
+            StatusLogOutput::Fifo(path) => {
+                let result = OpenOptions::new()
+                    .write(true)
+                    .custom_flags(OFlags::NONBLOCK.bits() as i32)
+                    .open(path);
+                match result {
+                    Ok(file) => Ok(Some(Box::new(file))),
+                    // No reader yet.
+                    Err(err)
+                        if err.raw_os_error() == Some(rustix::io::Errno::NXIO.raw_os_error()) =>
+                    {
+                        Ok(None)
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            StatusLogOutput::Command(command) => {
+                if self.child.is_some() {
+                    // Don't spawn another instance while the program is running but stopped reading. If it exited,
+                    // it is respawned after reaped on `SIGCHLD`.
+                    return Ok(None);
+                }
+                if self.respawn_at.is_some_and(|t| Instant::now() < t) {
+                    return Ok(None);
+                }
+                let mut child = Command::new("/bin/sh")
+                    .arg("-c")
+                    .arg(command)
+                    .stdin(Stdio::piped())
+                    .spawn()?;
+                let stdin = child.stdin.take().unwrap();
+                rustix::fs::fcntl_setfl(&stdin, OFlags::NONBLOCK)?;
+                self.child = Some(child);
+                self.spawned_at = Instant::now();
+                Ok(Some(Box::new(stdin)))
+            }
+        }
+    }
+}
+
+impl TatarajoState {
+    /// Writes the status with `Config::status_log` if it changed. Called on every action and focus/window change.
+    pub(crate) fn update_status_log(&mut self) {
+        if self.inner.status_logger.is_none() {
+            return;
+        }
+
+        let status = self.status();
+        if let Some(logger) = &mut self.inner.status_logger {
+            logger.log(&status);
+        }
+    }
+
+    /// Respawns the program of `StatusLogOutput::Command` if it exited. Called on `SIGCHLD`.
+    pub(crate) fn reap_status_log_program(&mut self) {
+        use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+
+        let Some(respawn_at) = self
+            .inner
+            .status_logger
+            .as_mut()
+            .and_then(|logger| logger.reap_child())
+        else {
+            return;
+        };
+        let result = self.inner.loop_handle.insert_source(
+            Timer::from_deadline(respawn_at),
+            |_, _, state| {
+                state.update_status_log();
+                TimeoutAction::Drop
+            },
+        );
+        if let Err(err) = result {
+            warn!("failed to schedule respawn of status log program: {}", err);
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        let view = &self.inner.view;
+        let stackset = view.stackset();
+        let current = stackset.workspaces.focused_index();
+        let workspaces = stackset
+            .workspaces
+            .as_vec()
+            .iter()
+            .enumerate()
+            .map(|(i, ws)| {
+                let windows = ws.stack.as_vec();
+                let state = if i == current {
+                    WorkspaceState::Current
+                } else if windows
+                    .iter()
+                    .any(|&wid| view.window(wid).is_some_and(|w| w.is_urgent()))
+                {
+                    WorkspaceState::Urgent
+                } else if !windows.is_empty() {
+                    WorkspaceState::NonEmpty
+                } else {
+                    WorkspaceState::Empty
+                };
+                WorkspaceStatus {
+                    tag: ws.tag.clone(),
+                    state,
+                }
+            })
+            .collect();
+
+        Status {
+            workspaces,
+            layout: view.layout_name(),
+            title: view.focused_window().and_then(|w| w.title()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn ws(tag: &str, state: WorkspaceState) -> WorkspaceStatus {
+        WorkspaceStatus {
+            tag: WorkspaceTag(tag.to_string()),
+            state,
+        }
+    }
+
+    #[rstest(
+        workspaces,
+        title,
+        expected,
+        case(
+            vec![
+                ws("1", WorkspaceState::Current),
+                ws("2", WorkspaceState::NonEmpty),
+                ws("3", WorkspaceState::Empty),
+                ws("4", WorkspaceState::Urgent),
+            ],
+            Some("emacs"),
+            "[1] 2 4* : LayoutFull : emacs"
+        ),
+        case(
+            vec![ws("1", WorkspaceState::Empty), ws("2", WorkspaceState::Current)],
+            None,
+            "[2] : LayoutFull"
+        )
+    )]
+    #[trace]
+    fn test_default_format(workspaces: Vec<WorkspaceStatus>, title: Option<&str>, expected: &str) {
+        let status = Status {
+            workspaces,
+            layout: "LayoutFull".to_string(),
+            title: title.map(String::from),
+        };
+        assert_eq!(status.default_format(), expected);
+    }
+}
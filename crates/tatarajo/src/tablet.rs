@@ -0,0 +1,65 @@
+use smithay::backend::input::TabletToolDescriptor;
+use smithay::input::pointer::CursorImageStatus;
+use smithay::utils::{IsAlive, Logical, Point};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Cursors of tablet tools.
+///
+/// Each tool has its own location and cursor image, which clients set with `zwp_tablet_tool_v2.set_cursor`.
+/// Note that tools don't move the pointer.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TabletToolCursors {
+    // Shared with the callback of `TabletSeatHandle::on_cursor_surface()`.
+    inner: Arc<Mutex<HashMap<TabletToolDescriptor, TabletToolCursor>>>,
+}
+
+#[derive(Debug)]
+struct TabletToolCursor {
+    /// `Some` iff the tool is in proximity.
+    location: Option<Point<f64, Logical>>,
+    status: CursorImageStatus,
+}
+
+impl Default for TabletToolCursor {
+    fn default() -> Self {
+        Self {
+            location: None,
+            status: CursorImageStatus::default_named(),
+        }
+    }
+}
+
+impl TabletToolCursors {
+    pub fn set_status(&self, tool: &TabletToolDescriptor, status: CursorImageStatus) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entry(tool.clone()).or_default().status = status;
+    }
+
+    pub fn set_location(&self, tool: &TabletToolDescriptor, location: Option<Point<f64, Logical>>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entry(tool.clone()).or_default().location = location;
+    }
+
+    pub fn location(&self, tool: &TabletToolDescriptor) -> Option<Point<f64, Logical>> {
+        let inner = self.inner.lock().unwrap();
+        inner.get(tool).and_then(|cursor| cursor.location)
+    }
+
+    /// Returns locations and cursor images of tools in proximity.
+    pub fn visible_cursors(&self) -> Vec<(Point<f64, Logical>, CursorImageStatus)> {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .values_mut()
+            .filter_map(|cursor| {
+                // Reset the cursor if the surface is no longer alive.
+                if let CursorImageStatus::Surface(surface) = &cursor.status {
+                    if !surface.alive() {
+                        cursor.status = CursorImageStatus::default_named();
+                    }
+                }
+                Some((cursor.location?, cursor.status.clone()))
+            })
+            .collect()
+    }
+}
@@ -179,7 +179,10 @@ impl<'a, T> Drop for FocusedVecUpdateGuard<'a, T> {
         let p: &mut FocusedVec<T> = unsafe { &mut *self.p };
         p.focus = self.focus;
 
-        p.assert_invariant();
+        // Don't panic while unwinding, which aborts. The state is restored by the catcher, e.g. `ActionFn::exec()`.
+        if !std::thread::panicking() {
+            p.assert_invariant();
+        }
     }
 }
 
@@ -203,7 +206,10 @@ impl<'a, T> Drop for NonEmptyFocusedVecUpdateGuard<'a, T> {
         let p: &mut NonEmptyFocusedVec<T> = unsafe { &mut *self.p };
         p.focus = self.focus;
 
-        p.assert_invariant();
+        // Don't panic while unwinding, which aborts. The state is restored by the catcher, e.g. `ActionFn::exec()`.
+        if !std::thread::panicking() {
+            p.assert_invariant();
+        }
     }
 }
 
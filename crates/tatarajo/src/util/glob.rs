@@ -0,0 +1,54 @@
+/// Matches `s` with a glob `pattern`. `*` matches any string and `?` matches any character.
+pub(crate) fn glob_match(pattern: &str, s: &str) -> bool {
+    let p = pattern.chars().collect::<Vec<_>>();
+    let s = s.chars().collect::<Vec<_>>();
+
+    // Backtracking only to the last `*` is sufficient.
+    let (mut i, mut j) = (0, 0);
+    let mut last_star = None;
+    while j < s.len() {
+        if i < p.len() && (p[i] == '?' || p[i] == s[j]) {
+            i += 1;
+            j += 1;
+        } else if i < p.len() && p[i] == '*' {
+            last_star = Some((i, j));
+            i += 1;
+        } else if let Some((si, sj)) = last_star {
+            i = si + 1;
+            j = sj + 1;
+            last_star = Some((si, sj + 1));
+        } else {
+            return false;
+        }
+    }
+    p[i..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        pattern,
+        s,
+        expected,
+        case("", "", true),
+        case("", "a", false),
+        case("*", "", true),
+        case("*", "SynPS/2 Synaptics TouchPad", true),
+        case("*TouchPad", "SynPS/2 Synaptics TouchPad", true),
+        case("*Touchpad*", "SynPS/2 Synaptics TouchPad", false),
+        case("Syn*Touch*", "SynPS/2 Synaptics TouchPad", true),
+        case("a?c", "abc", true),
+        case("a?c", "ac", false),
+        case("a*b*c", "aXbYbZc", true),
+        case("a*b*c", "aXbYbZ", false),
+        case("abc", "abc", true),
+        case("abc", "abcd", false)
+    )]
+    #[trace]
+    fn test_glob_match(pattern: &str, s: &str, expected: bool) {
+        assert_eq!(glob_match(pattern, s), expected);
+    }
+}
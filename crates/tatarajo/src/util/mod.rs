@@ -1,4 +1,5 @@
 mod focused_vec;
+pub(crate) mod glob;
 mod id;
 pub(crate) mod panic;
 
@@ -9,3 +10,22 @@ pub use id::*;
 pub(crate) trait EventHandler<Event> {
     fn handle_event(&mut self, event: Event);
 }
+
+/// Returns the type name without the module path and generic parameters, e.g. `LayoutFull`.
+pub(crate) fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(short_type_name::<String>(), "String");
+        assert_eq!(short_type_name::<Id<String>>(), "Id");
+        assert_eq!(short_type_name::<Vec<Id<String>>>(), "Vec");
+    }
+}
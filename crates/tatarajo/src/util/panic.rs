@@ -1,20 +1,24 @@
 use big_s::S;
 use std::fmt;
-use std::sync::OnceLock;
+use std::sync::Mutex;
 use std::thread::ThreadId;
 
+/// The thread that panicked first. Later panics are logged only if they are in the same thread, as they may be caused
+/// by unwinding.
+static ROOT_CAUSE_THREAD_ID: Mutex<Option<ThreadId>> = Mutex::new(None);
+
 /// Set panic hook, which log a backtrace with `tracing` macro.
 pub(crate) fn set_hook() {
-    static ROOT_CAUSE_THREAD_ID: OnceLock<ThreadId> = OnceLock::new();
-
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
-        let mut initialized = false;
-        let root_cause_thread_id = ROOT_CAUSE_THREAD_ID.get_or_init(|| {
-            initialized = true;
-            std::thread::current().id()
-        });
-        if *root_cause_thread_id == std::thread::current().id() {
+        // Don't hold the lock while logging, which may panic again.
+        let (initialized, root_cause_thread_id) = {
+            let mut id = ROOT_CAUSE_THREAD_ID
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            (id.is_none(), *id.get_or_insert(std::thread::current().id()))
+        };
+        if root_cause_thread_id == std::thread::current().id() {
             let maybe_unwinding = if initialized {
                 ""
             } else {
@@ -34,6 +38,14 @@ backtrace:
     }));
 }
 
+/// Forgets the last panic. Call it after a panic is caught and unwinding finished, so that the next panic is reported
+/// as a root cause.
+pub(crate) fn clear_root_cause() {
+    *ROOT_CAUSE_THREAD_ID
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = None;
+}
+
 // Helper to show the backtrace of actual cause of panic hook.
 struct BacktraceAltFormatter(backtrace::Backtrace);
 
@@ -2,9 +2,11 @@ use crate::util::Id;
 use crate::view::stackset::StackSet;
 use crate::view::window::{Window, WindowProps};
 use smithay::utils::{Logical, Rectangle};
+use std::collections::HashMap;
 
 pub struct ViewLayoutApi<'state> {
     pub(super) stackset: &'state StackSet,
+    pub(super) windows: &'state HashMap<Id<Window>, Window>,
     pub(super) rect: Rectangle<i32, Logical>,
     pub(super) layout_queue: Vec<(Id<Window>, WindowProps)>,
 }
@@ -14,6 +16,10 @@ impl ViewLayoutApi<'_> {
         self.stackset
     }
 
+    pub fn window(&self, id: Id<Window>) -> Option<&Window> {
+        self.windows.get(&id)
+    }
+
     pub fn rect(&self) -> &Rectangle<i32, Logical> {
         &self.rect
     }
@@ -3,6 +3,11 @@ use crate::view::api::ViewLayoutApi;
 
 pub trait LayoutNodeI {
     fn layout(&self, api: &mut ViewLayoutApi<'_>);
+
+    /// Human readable name, e.g. `LayoutFull`. It is shown in the status log.
+    fn name(&self) -> String {
+        crate::util::short_type_name::<Self>().to_string()
+    }
 }
 
 pub struct LayoutNode {
@@ -30,4 +35,8 @@ impl LayoutNode {
     pub fn layout(&self, api: &mut ViewLayoutApi<'_>) {
         self.inner.layout(api);
     }
+
+    pub fn name(&self) -> String {
+        self.inner.name()
+    }
 }
@@ -1,16 +1,40 @@
 use crate::util::{FocusedVec, Id, NonEmptyFocusedVec};
 use crate::view::window::Window;
 
+#[derive(Clone)]
 pub struct StackSet {
     pub workspaces: NonEmptyFocusedVec<Workspace>,
+    /// Tags of workspaces, most recently focused first.
+    pub(super) workspace_history: Vec<WorkspaceTag>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WorkspaceTag(pub String);
 
+impl std::fmt::Display for WorkspaceTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WorkspaceError {
+    #[error("workspace not found: {0}")]
+    NotFound(WorkspaceTag),
+    #[error("workspace already exists: {0}")]
+    AlreadyExists(WorkspaceTag),
+    #[error("workspace is not empty: {0}")]
+    NotEmpty(WorkspaceTag),
+    #[error("the last workspace can't be removed")]
+    Last,
+}
+
+#[derive(Clone)]
 pub struct Workspace {
     pub tag: WorkspaceTag,
     pub stack: FocusedVec<Id<Window>>,
+    /// Windows in `stack`, most recently focused first. Windows never focused are not in it.
+    pub(super) history: Vec<Id<Window>>,
 }
 
 impl StackSet {
@@ -20,19 +44,314 @@ impl StackSet {
             .map(|tag| Workspace {
                 tag,
                 stack: FocusedVec::default(),
+                history: vec![],
             })
             .collect();
         let workspaces = NonEmptyFocusedVec::new(workspaces, 0);
-        Self { workspaces }
+        Self {
+            workspaces,
+            workspace_history: vec![],
+        }
     }
 
     pub fn workspaces(&self) -> &NonEmptyFocusedVec<Workspace> {
         &self.workspaces
     }
+
+    pub fn workspace_history(&self) -> &[WorkspaceTag] {
+        &self.workspace_history
+    }
+
+    pub fn workspace_index(&self, tag: &WorkspaceTag) -> Result<usize, WorkspaceError> {
+        self.workspaces
+            .as_vec()
+            .iter()
+            .position(|ws| ws.tag == *tag)
+            .ok_or_else(|| WorkspaceError::NotFound(tag.clone()))
+    }
+
+    /// Adds an empty workspace next to the focused one and focuses it.
+    ///
+    /// If `tag` is `None`, the smallest positive number not used as a tag is used.
+    pub fn add_workspace(&mut self, tag: Option<WorkspaceTag>) -> Result<(), WorkspaceError> {
+        let tag = match tag {
+            Some(tag) => {
+                if self.workspace_index(&tag).is_ok() {
+                    return Err(WorkspaceError::AlreadyExists(tag));
+                }
+                tag
+            }
+            None => (1..)
+                .map(|i| WorkspaceTag(i.to_string()))
+                .find(|tag| self.workspace_index(tag).is_err())
+                .unwrap(/* Tags are finitely many. */),
+        };
+
+        let mut workspaces = self.workspaces.as_mut();
+        let i = workspaces.focus + 1;
+        workspaces.vec.insert(
+            i,
+            Workspace {
+                tag,
+                stack: FocusedVec::default(),
+                history: vec![],
+            },
+        );
+        workspaces.focus = i;
+        workspaces.commit();
+
+        Ok(())
+    }
+
+    /// Renames the focused workspace.
+    pub fn rename_workspace(&mut self, tag: WorkspaceTag) -> Result<(), WorkspaceError> {
+        if self.workspace_index(&tag).is_ok() {
+            return Err(WorkspaceError::AlreadyExists(tag));
+        }
+
+        let ws = self.workspaces.focus_mut();
+        for t in &mut self.workspace_history {
+            if *t == ws.tag {
+                *t = tag.clone();
+            }
+        }
+        ws.tag = tag;
+
+        Ok(())
+    }
+
+    /// Removes the focused workspace and focuses the most recently focused one.
+    ///
+    /// If `merge` is true, windows of the removed workspace are moved to the newly focused one. Otherwise, it fails if
+    /// the workspace is not empty.
+    pub fn remove_workspace(&mut self, merge: bool) -> Result<(), WorkspaceError> {
+        if self.workspaces.len() == 1 {
+            return Err(WorkspaceError::Last);
+        }
+        let src = self.workspaces.focused_index();
+        let src_tag = self.workspaces.focus().tag.clone();
+        if !merge && !self.workspaces.focus().stack.is_empty() {
+            return Err(WorkspaceError::NotEmpty(src_tag));
+        }
+
+        let dst = self
+            .workspace_history
+            .iter()
+            .filter(|&tag| *tag != src_tag)
+            .find_map(|tag| self.workspace_index(tag).ok())
+            .unwrap_or(if src == 0 { 1 } else { src - 1 });
+
+        let mut workspaces = self.workspaces.as_mut();
+        let removed = workspaces.vec.remove(src);
+        let dst = if dst > src { dst - 1 } else { dst };
+        let stack = workspaces.vec[dst].stack.as_mut();
+        stack.vec.extend(removed.stack.as_vec().iter().copied());
+        stack.commit();
+        workspaces.focus = dst;
+        workspaces.commit();
+
+        self.workspace_history.retain(|tag| *tag != src_tag);
+
+        Ok(())
+    }
+
+    /// Moves the focused workspace by `diff` in the order of workspaces, cyclically.
+    pub fn move_workspace(&mut self, diff: isize) {
+        let mut workspaces = self.workspaces.as_mut();
+        let i = workspaces.focus;
+        let j = workspaces.mod_plus_focused_index(diff);
+        let ws = workspaces.vec.remove(i);
+        workspaces.vec.insert(j, ws);
+        workspaces.focus = j;
+        workspaces.commit();
+    }
+
+    /// Records the current focus to histories.
+    ///
+    /// Windows are dropped from histories of workspaces that no longer have them, so that histories are consistent
+    /// with windows moved between workspaces.
+    pub(super) fn update_history(&mut self) {
+        let workspaces = self.workspaces.as_mut();
+        for ws in workspaces.vec.iter_mut() {
+            let stack = ws.stack.as_vec();
+            ws.history.retain(|wid| stack.contains(wid));
+            if let Some(&focus) = ws.stack.focus() {
+                ws.history.retain(|&wid| wid != focus);
+                ws.history.insert(0, focus);
+            }
+        }
+        workspaces.commit();
+
+        let tag = self.workspaces.focus().tag.clone();
+        let workspaces = self.workspaces.as_vec();
+        self.workspace_history
+            .retain(|t| *t != tag && workspaces.iter().any(|ws| ws.tag == *t));
+        self.workspace_history.insert(0, tag);
+    }
 }
 
 impl Workspace {
     pub fn stack(&self) -> &FocusedVec<Id<Window>> {
         &self.stack
     }
+
+    pub fn history(&self) -> &[Id<Window>] {
+        &self.history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stackset(tags: &[&str]) -> StackSet {
+        StackSet::new(tags.iter().map(|t| WorkspaceTag(t.to_string())).collect())
+    }
+
+    fn tags(ss: &StackSet) -> Vec<&str> {
+        ss.workspaces
+            .as_vec()
+            .iter()
+            .map(|ws| ws.tag.0.as_str())
+            .collect()
+    }
+
+    fn history(ss: &StackSet) -> Vec<&str> {
+        ss.workspace_history.iter().map(|t| t.0.as_str()).collect()
+    }
+
+    #[test]
+    fn test_update_history() {
+        let [a, b, c] = [Id::from(0), Id::from(1), Id::from(2)];
+        let mut ss = stackset(&["1", "2", "3"]);
+        ss.workspaces.focus_mut().stack = FocusedVec::new(vec![a, b, c], 0);
+
+        ss.update_history();
+        assert_eq!(ss.workspaces.focus().history, vec![a]);
+        assert_eq!(history(&ss), vec!["1"]);
+
+        ss.workspaces.focus_mut().stack.set_focused_index(2);
+        ss.update_history();
+        assert_eq!(ss.workspaces.focus().history, vec![c, a]);
+
+        ss.workspaces.focus_mut().stack.set_focused_index(0);
+        ss.update_history();
+        assert_eq!(ss.workspaces.focus().history, vec![a, c]);
+
+        // Windows no longer in the workspace are dropped.
+        ss.workspaces.focus_mut().stack = FocusedVec::new(vec![a, b], 1);
+        ss.update_history();
+        assert_eq!(ss.workspaces.focus().history, vec![b, a]);
+
+        ss.workspaces.set_focused_index(2);
+        ss.update_history();
+        assert_eq!(history(&ss), vec!["3", "1"]);
+        // An empty workspace has no window history.
+        assert!(ss.workspaces.focus().history.is_empty());
+
+        ss.workspaces.set_focused_index(1);
+        ss.update_history();
+        ss.workspaces.set_focused_index(2);
+        ss.update_history();
+        assert_eq!(history(&ss), vec!["3", "2", "1"]);
+        // Histories of unfocused workspaces are kept.
+        assert_eq!(ss.workspaces.as_vec()[0].history, vec![b, a]);
+    }
+
+    #[test]
+    fn test_add_and_rename_workspace() {
+        let mut ss = stackset(&["1", "3"]);
+
+        ss.add_workspace(None).unwrap();
+        assert_eq!(tags(&ss), vec!["1", "2", "3"]);
+        assert_eq!(ss.workspaces.focused_index(), 1);
+
+        ss.workspaces.set_focused_index(2);
+        ss.add_workspace(Some(WorkspaceTag("web".into()))).unwrap();
+        assert_eq!(tags(&ss), vec!["1", "2", "3", "web"]);
+        assert_eq!(ss.workspaces.focused_index(), 3);
+        assert!(matches!(
+            ss.add_workspace(Some(WorkspaceTag("1".into()))),
+            Err(WorkspaceError::AlreadyExists(_))
+        ));
+
+        ss.update_history();
+        ss.rename_workspace(WorkspaceTag("mail".into())).unwrap();
+        assert_eq!(tags(&ss), vec!["1", "2", "3", "mail"]);
+        assert_eq!(history(&ss), vec!["mail"]);
+        assert!(matches!(
+            ss.rename_workspace(WorkspaceTag("2".into())),
+            Err(WorkspaceError::AlreadyExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_remove_workspace() {
+        let [a, b] = [Id::from(0), Id::from(1)];
+
+        // The most recently focused workspace is focused.
+        let mut ss = stackset(&["1", "2", "3", "4"]);
+        for i in [3, 0, 1] {
+            ss.workspaces.set_focused_index(i);
+            ss.update_history();
+        }
+        ss.remove_workspace(false).unwrap();
+        assert_eq!(tags(&ss), vec!["1", "3", "4"]);
+        assert_eq!(ss.workspaces.focus().tag.0, "1");
+        assert_eq!(history(&ss), vec!["1", "4"]);
+
+        // Windows are merged into the newly focused workspace.
+        ss.workspaces.set_focused_index(2);
+        ss.workspaces.focus_mut().stack = FocusedVec::new(vec![b], 0);
+        ss.workspaces.as_mut().vec[0].stack = FocusedVec::new(vec![a], 0);
+        assert!(matches!(
+            ss.remove_workspace(false),
+            Err(WorkspaceError::NotEmpty(_))
+        ));
+        ss.remove_workspace(true).unwrap();
+        assert_eq!(tags(&ss), vec!["1", "3"]);
+        assert_eq!(ss.workspaces.focused_index(), 0);
+        assert_eq!(ss.workspaces.focus().stack.as_vec(), &vec![a, b]);
+        assert_eq!(ss.workspaces.focus().stack.focus(), Some(&a));
+
+        // Without history, the previous one, or the next one of the first, is focused.
+        let mut ss = stackset(&["1", "2", "3"]);
+        ss.workspaces.set_focused_index(2);
+        ss.remove_workspace(false).unwrap();
+        assert_eq!(tags(&ss), vec!["1", "2"]);
+        assert_eq!(ss.workspaces.focused_index(), 1);
+        ss.workspaces.set_focused_index(0);
+        ss.remove_workspace(false).unwrap();
+        assert_eq!(tags(&ss), vec!["2"]);
+        assert_eq!(ss.workspaces.focused_index(), 0);
+
+        // The only workspace can't be removed.
+        assert!(matches!(
+            ss.remove_workspace(true),
+            Err(WorkspaceError::Last)
+        ));
+        assert_eq!(tags(&ss), vec!["2"]);
+    }
+
+    #[test]
+    fn test_move_workspace() {
+        let mut ss = stackset(&["1", "2", "3"]);
+
+        ss.move_workspace(1);
+        assert_eq!(tags(&ss), vec!["2", "1", "3"]);
+        assert_eq!(ss.workspaces.focused_index(), 1);
+
+        ss.move_workspace(1);
+        assert_eq!(tags(&ss), vec!["2", "3", "1"]);
+        assert_eq!(ss.workspaces.focused_index(), 2);
+
+        // Moving across the end wraps around.
+        ss.move_workspace(1);
+        assert_eq!(tags(&ss), vec!["1", "2", "3"]);
+        assert_eq!(ss.workspaces.focused_index(), 0);
+
+        ss.move_workspace(-1);
+        assert_eq!(tags(&ss), vec!["2", "3", "1"]);
+        assert_eq!(ss.workspaces.focused_index(), 2);
+    }
 }
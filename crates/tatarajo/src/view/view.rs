@@ -2,7 +2,7 @@ use crate::util::{FocusedVec, Id};
 use crate::view::api::ViewLayoutApi;
 use crate::view::layout_node::LayoutNode;
 use crate::view::predefined::LayoutFull;
-use crate::view::stackset::{StackSet, WorkspaceTag};
+use crate::view::stackset::{StackSet, WorkspaceError, WorkspaceTag};
 use crate::view::window::Window;
 use itertools::Itertools;
 use smithay::utils::{Logical, Rectangle, Size};
@@ -18,6 +18,32 @@ pub(super) struct ViewState {
     pub(super) node: LayoutNode,
     pub(super) windows: HashMap<Id<Window>, Window>,
     pub(super) rect: Rectangle<i32, Logical>,
+    /// Geometries of windows given by the last layout pass.
+    pub(super) layout_geometries: Vec<(Id<Window>, Rectangle<i32, Logical>)>,
+    /// Nothing is focused even if the focused workspace has windows. See `View::clear_focus()`.
+    pub(super) focus_cleared: bool,
+}
+
+/// Where a new window is inserted in the stack of the focused workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertPolicy {
+    /// Just before the focused window, i.e. nearer to master.
+    AboveFocus,
+    /// Just after the focused window.
+    BelowFocus,
+    /// At the head of the stack.
+    Master,
+    /// At the tail of the stack.
+    End,
+}
+
+/// Direction on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
 }
 
 impl View {
@@ -31,6 +57,8 @@ impl View {
             node,
             windows: HashMap::new(),
             rect,
+            layout_geometries: vec![],
+            focus_cleared: false,
         };
         Self { state }
     }
@@ -47,6 +75,20 @@ impl View {
         self.state.windows.get(&window_id)
     }
 
+    pub fn layout_name(&self) -> String {
+        self.state.node.name()
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = &Window> {
+        self.state.windows.values()
+    }
+
+    /// Returns the window `window_id` is transient for, e.g. the main window of a dialog.
+    pub fn parent_window(&self, window_id: Id<Window>) -> Option<&Window> {
+        let window = self.window(window_id)?;
+        self.windows().find(|w| w.is_parent_of(window))
+    }
+
     // Returns true iff self is changed.
     pub fn refresh(&mut self, space: &mut smithay::desktop::Space<Window>) -> bool {
         use smithay::utils::IsAlive;
@@ -72,7 +114,7 @@ impl View {
 
         // Speed: In normal use cases, we expect `removed_window_ids.len()` is very small and avoid using `HashSet`.
         //
-        // TODO: Support other focus policies, e.g. seeing backforward first.
+        // Used if no window in the focus history remains.
         let calc_focus = |stack: &FocusedVec<Id<Window>>, i: usize| -> Option<Id<Window>> {
             debug_assert!(i < stack.len() || i == 0);
 
@@ -94,7 +136,22 @@ impl View {
             None
         };
         for workspace in self.state.stackset.workspaces.as_mut().vec.iter_mut() {
-            let focus = calc_focus(&workspace.stack, workspace.stack.focused_index());
+            workspace
+                .history
+                .retain(|wid| !removed_window_ids.contains(wid));
+            let focus = workspace
+                .stack
+                .focus()
+                .filter(|&wid| !removed_window_ids.contains(wid))
+                .copied()
+                .or_else(|| {
+                    workspace
+                        .history
+                        .iter()
+                        .find(|wid| workspace.stack.as_vec().contains(wid))
+                        .copied()
+                })
+                .or_else(|| calc_focus(&workspace.stack, workspace.stack.focused_index()));
             let mut stack = workspace.stack.as_mut();
             stack.vec.retain(|wid| !removed_window_ids.contains(wid));
             stack.focus = focus
@@ -118,11 +175,16 @@ impl View {
         let rect = self.state.rect;
         let mut api = ViewLayoutApi {
             stackset: &self.state.stackset,
+            windows: &self.state.windows,
             rect,
             layout_queue: vec![],
         };
         self.state.node.layout(&mut api);
         let layout_queue = api.layout_queue;
+        self.state.layout_geometries = layout_queue
+            .iter()
+            .map(|(window_id, props)| (*window_id, props.geometry))
+            .collect();
 
         // Remove windows from the space that are not in layout result.
         let mut removing_window_ids = space.elements().map(|w| w.id()).collect::<HashSet<_>>();
@@ -165,20 +227,77 @@ impl View {
         self.layout(space);
     }
 
-    pub fn register_window(&mut self, smithay_window: smithay::desktop::Window) -> Id<Window> {
-        let window = Window::new(smithay_window);
+    /// Registers a window to the workspace `workspace`, or the focused one if it is `None` or doesn't exist.
+    pub fn register_window(
+        &mut self,
+        window: Window,
+        insert_policy: InsertPolicy,
+        workspace: Option<&WorkspaceTag>,
+    ) -> Id<Window> {
         let window_id = window.id();
-        self.state
-            .stackset
-            .workspaces
-            .focus_mut()
-            .stack
-            .push(window_id);
+
+        let workspaces = &mut self.state.stackset.workspaces;
+        let ws_index = workspace
+            .and_then(|tag| workspaces.as_vec().iter().position(|ws| ws.tag == *tag))
+            .unwrap_or_else(|| workspaces.focused_index());
+        let workspaces = workspaces.as_mut();
+        let mut stack = workspaces.vec[ws_index].stack.as_mut();
+        let i = match insert_policy {
+            InsertPolicy::AboveFocus => stack.focus,
+            InsertPolicy::BelowFocus => (stack.focus + 1).min(stack.vec.len()),
+            InsertPolicy::Master => 0,
+            InsertPolicy::End => stack.vec.len(),
+        };
+        stack.vec.insert(i, window_id);
+        // Keep focus on the same window.
+        if stack.vec.len() > 1 && i <= stack.focus {
+            stack.focus += 1;
+        }
+        stack.commit();
+        workspaces.commit();
+
         self.state.windows.insert(window_id, window);
 
         window_id
     }
 
+    /// Moves a window to the workspace `tag` without changing the focused workspace. It is focused in the workspace.
+    pub fn move_window_to_workspace(
+        &mut self,
+        id: Id<Window>,
+        tag: &WorkspaceTag,
+    ) -> Result<(), WorkspaceError> {
+        let dst = self.state.stackset.workspace_index(tag)?;
+        let workspaces = self.state.stackset.workspaces.as_mut();
+        let Some(src) = workspaces
+            .vec
+            .iter()
+            .position(|ws| ws.stack.as_vec().contains(&id))
+        else {
+            return Ok(());
+        };
+        if src == dst {
+            return Ok(());
+        }
+
+        let mut stack = workspaces.vec[src].stack.as_mut();
+        let i = stack.vec.iter().position(|&wid| wid == id).unwrap();
+        stack.vec.remove(i);
+        if i < stack.focus || stack.focus >= stack.vec.len() {
+            stack.focus = stack.focus.saturating_sub(1);
+        }
+        stack.commit();
+
+        let stack = workspaces.vec[dst].stack.as_mut();
+        let focus = stack.focus;
+        stack.vec.insert(focus, id);
+        stack.commit();
+
+        workspaces.commit();
+
+        Ok(())
+    }
+
     pub fn set_focus(&mut self, id: Id<Window>) {
         let workspaces = &mut self.state.stackset.workspaces;
 
@@ -197,9 +316,19 @@ impl View {
 
         workspaces.set_focused_index(i);
         workspaces.focus_mut().stack.set_focused_index(j);
+        self.state.focus_cleared = false;
+    }
+
+    /// Makes `focused_window()` return `None` until focus is set again by `set_focus()` or `update_stackset_with()`,
+    /// e.g. while the pointer is on no window with `FocusPolicy::Strict`.
+    pub fn clear_focus(&mut self) {
+        self.state.focus_cleared = true;
     }
 
     pub fn focused_window(&self) -> Option<&Window> {
+        if self.state.focus_cleared {
+            return None;
+        }
         self.state
             .stackset
             .workspaces
@@ -210,6 +339,9 @@ impl View {
     }
 
     pub fn focused_window_mut(&mut self) -> Option<&mut Window> {
+        if self.state.focus_cleared {
+            return None;
+        }
         self.state
             .stackset
             .workspaces
@@ -219,7 +351,165 @@ impl View {
             .map(|id| self.state.windows.get_mut(id).unwrap())
     }
 
-    pub fn update_stackset_with(&mut self, f: impl FnOnce(&mut StackSet)) {
-        f(&mut self.state.stackset);
+    /// Returns the window nearest to the focused one in `direction`, based on geometries of the last layout pass.
+    ///
+    /// If no window is in `direction`, falls back to the nearest window in `space` on the adjacent output.
+    pub fn window_in_direction(
+        &self,
+        direction: Direction,
+        space: &smithay::desktop::Space<Window>,
+    ) -> Option<Id<Window>> {
+        let &focus = self.state.stackset.workspaces.focus().stack.focus()?;
+        let &(_, from) = self
+            .state
+            .layout_geometries
+            .iter()
+            .find(|(window_id, _)| *window_id == focus)?;
+        let candidates = self
+            .state
+            .layout_geometries
+            .iter()
+            .filter(|(window_id, _)| *window_id != focus)
+            .copied();
+        nearest_in_direction(from, candidates, direction).or_else(|| {
+            let outputs = space
+                .outputs()
+                .filter_map(|output| space.output_geometry(output))
+                .collect_vec();
+            let windows = space
+                .elements()
+                .filter(|window| window.id() != focus)
+                .filter_map(|window| Some((window.id(), space.element_geometry(window)?)));
+            nearest_on_adjacent_output(from, &outputs, windows, direction)
+        })
+    }
+
+    /// Records the current focus to the focus histories of windows and workspaces.
+    pub fn update_focus_history(&mut self) {
+        self.state.stackset.update_history();
+    }
+
+    pub fn update_stackset_with<R>(&mut self, f: impl FnOnce(&mut StackSet) -> R) -> R {
+        self.state.focus_cleared = false;
+        f(&mut self.state.stackset)
+    }
+}
+
+/// Returns the candidate nearest to `from` in `direction`.
+///
+/// Candidates whose centers are not in `direction` are ignored. Deviation perpendicular to `direction` is weighted, so
+/// that a window right next to `from` wins over a diagonal one.
+fn nearest_in_direction<T>(
+    from: Rectangle<i32, Logical>,
+    candidates: impl IntoIterator<Item = (T, Rectangle<i32, Logical>)>,
+    direction: Direction,
+) -> Option<T> {
+    fn center(rect: &Rectangle<i32, Logical>) -> (f64, f64) {
+        (
+            rect.loc.x as f64 + rect.size.w as f64 / 2.0,
+            rect.loc.y as f64 + rect.size.h as f64 / 2.0,
+        )
+    }
+
+    let (fx, fy) = center(&from);
+    candidates
+        .into_iter()
+        .filter_map(|(x, rect)| {
+            let (cx, cy) = center(&rect);
+            let (along, across) = match direction {
+                Direction::Left => (fx - cx, cy - fy),
+                Direction::Right => (cx - fx, cy - fy),
+                Direction::Up => (fy - cy, cx - fx),
+                Direction::Down => (cy - fy, cx - fx),
+            };
+            (along > 0.0).then_some((x, along + 2.0 * across.abs()))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(x, _)| x)
+}
+
+/// Returns the candidate nearest to `from` among ones on the output next to the one of `from` in `direction`.
+fn nearest_on_adjacent_output<T>(
+    from: Rectangle<i32, Logical>,
+    outputs: &[Rectangle<i32, Logical>],
+    candidates: impl IntoIterator<Item = (T, Rectangle<i32, Logical>)>,
+    direction: Direction,
+) -> Option<T> {
+    let current = outputs
+        .iter()
+        .copied()
+        .filter(|output| output.overlaps(from))
+        .max_by_key(|output| output.intersection(from).map_or(0, |r| r.size.w * r.size.h))?;
+    let others = outputs.iter().copied().filter(|&output| output != current);
+    let adjacent = nearest_in_direction(current, others.map(|output| (output, output)), direction)?;
+    let candidates = candidates
+        .into_iter()
+        .filter(|(_, rect)| rect.overlaps(adjacent));
+    nearest_in_direction(from, candidates, direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((x, y), (w, h))
+    }
+
+    #[test]
+    fn test_nearest_in_direction() {
+        // +---+---+
+        // | a | b |
+        // |   +---+
+        // |   | c |
+        // +---+---+---+
+        //             | d (another output)
+        let a = rect(0, 0, 100, 200);
+        let b = rect(100, 0, 100, 100);
+        let c = rect(100, 100, 100, 100);
+        let d = rect(200, 200, 100, 100);
+        let windows = [("a", a), ("b", b), ("c", c), ("d", d)];
+        let nearest = |from: Rectangle<i32, Logical>, direction| {
+            let candidates = windows.iter().copied().filter(|&(_, r)| r != from);
+            nearest_in_direction(from, candidates, direction)
+        };
+
+        assert_eq!(nearest(a, Direction::Right), Some("b"));
+        assert_eq!(nearest(a, Direction::Left), None);
+        assert_eq!(nearest(b, Direction::Down), Some("c"));
+        assert_eq!(nearest(b, Direction::Left), Some("a"));
+        assert_eq!(nearest(c, Direction::Left), Some("a"));
+        assert_eq!(nearest(c, Direction::Up), Some("b"));
+        assert_eq!(nearest(c, Direction::Right), Some("d"));
+        assert_eq!(nearest(d, Direction::Up), Some("c"));
+    }
+
+    #[test]
+    fn test_nearest_on_adjacent_output() {
+        // +-------+-------+
+        // | a | b | c     |
+        // +---+---+-------+
+        // |   | d |
+        // +---+---+
+        let left = rect(0, 0, 200, 100);
+        let right = rect(200, 0, 200, 100);
+        let below = rect(0, 100, 200, 100);
+        let outputs = [left, right, below];
+        let a = rect(0, 0, 100, 100);
+        let b = rect(100, 0, 100, 100);
+        let c = rect(200, 0, 200, 100);
+        let d = rect(100, 100, 100, 100);
+        let windows = [("a", a), ("b", b), ("c", c), ("d", d)];
+        let nearest = |from: Rectangle<i32, Logical>, direction| {
+            let candidates = windows.iter().copied().filter(|&(_, r)| r != from);
+            nearest_on_adjacent_output(from, &outputs, candidates, direction)
+        };
+
+        assert_eq!(nearest(a, Direction::Right), Some("c"));
+        assert_eq!(nearest(b, Direction::Down), Some("d"));
+        assert_eq!(nearest(c, Direction::Left), Some("b"));
+        assert_eq!(nearest(d, Direction::Up), Some("b"));
+        assert_eq!(nearest(a, Direction::Left), None);
+        assert_eq!(nearest(d, Direction::Down), None);
     }
 }
@@ -54,7 +54,10 @@ mod window {
     use crate::util::Id;
     use itertools::Itertools;
     use smithay::desktop::space::SpaceElement;
-    use smithay::utils::{IsAlive, Logical, Physical, Point, Rectangle, Scale};
+    use smithay::utils::{IsAlive, Logical, Physical, Point, Rectangle, Scale, Size};
+    use smithay::wayland::compositor::with_states;
+    use smithay::wayland::seat::WaylandFocus;
+    use smithay::wayland::shell::xdg::{SurfaceCachedState, XdgToplevelSurfaceData};
     use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
@@ -66,10 +69,21 @@ mod window {
         id: Id<Window>,
         inner: Arc<Mutex<WindowInner>>,
         swindow: smithay::desktop::Window,
+        pid: Option<u32>,
     }
 
     struct WindowInner {
         props: WindowProps,
+        /// Set by tatarajo, e.g. on an activation request. X11 windows can also set it by themselves.
+        urgent: bool,
+    }
+
+    /// Metadata of a window that is notified when it changes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum WindowMetadataChange {
+        Title,
+        AppId,
+        Urgency,
     }
 
     impl PartialEq for Window {
@@ -83,16 +97,19 @@ mod window {
     impl Eq for Window {}
 
     impl Window {
-        pub fn new(swindow: smithay::desktop::Window) -> Self {
+        /// `pid` is the pid of the client, which backends know.
+        pub fn new(swindow: smithay::desktop::Window, pid: Option<u32>) -> Self {
             let geometry = swindow.geometry();
             let inner = WindowInner {
                 props: WindowProps { geometry },
+                urgent: false,
             };
             let inner = Arc::new(Mutex::new(inner));
             Self {
                 id: Id::new(),
                 inner,
                 swindow,
+                pid,
             }
         }
 
@@ -109,10 +126,110 @@ mod window {
             self.swindow.toplevel()
         }
 
+        pub fn is_x11(&self) -> bool {
+            self.swindow.x11_surface().is_some()
+        }
+
+        /// Returns `app_id` of an xdg toplevel, or the class of `WM_CLASS` of an X11 window.
+        pub fn app_id(&self) -> Option<String> {
+            if let Some(toplevel) = self.swindow.toplevel() {
+                return with_states(toplevel.wl_surface(), |states| {
+                    let data = states.data_map.get::<XdgToplevelSurfaceData>()?;
+                    data.lock().unwrap().app_id.clone()
+                });
+            }
+            self.class()
+        }
+
+        pub fn title(&self) -> Option<String> {
+            if let Some(toplevel) = self.swindow.toplevel() {
+                return with_states(toplevel.wl_surface(), |states| {
+                    let data = states.data_map.get::<XdgToplevelSurfaceData>()?;
+                    data.lock().unwrap().title.clone()
+                });
+            }
+            self.swindow.x11_surface().map(|surface| surface.title())
+        }
+
+        /// Returns the class of `WM_CLASS` of an X11 window. `None` for Wayland windows.
+        pub fn class(&self) -> Option<String> {
+            self.swindow.x11_surface().map(|surface| surface.class())
+        }
+
+        pub fn pid(&self) -> Option<u32> {
+            self.pid
+        }
+
+        /// Returns true iff `child` is a transient window of `self`, e.g. a dialog.
+        pub fn is_parent_of(&self, child: &Window) -> bool {
+            if let Some(toplevel) = child.swindow.toplevel() {
+                return toplevel
+                    .parent()
+                    .is_some_and(|parent| self.swindow.wl_surface().is_some_and(|s| s == parent));
+            }
+            if let (Some(child), Some(parent)) =
+                (child.swindow.x11_surface(), self.swindow.x11_surface())
+            {
+                return child.is_transient_for() == Some(parent.window_id());
+            }
+            false
+        }
+
+        /// Returns the minimum size the window requests. `None` if it has no limit.
+        pub fn min_size(&self) -> Option<Size<i32, Logical>> {
+            if let Some(toplevel) = self.swindow.toplevel() {
+                let size = with_states(toplevel.wl_surface(), |states| {
+                    states.cached_state.current::<SurfaceCachedState>().min_size
+                });
+                return (size.w > 0 || size.h > 0).then_some(size);
+            }
+            self.swindow
+                .x11_surface()
+                .and_then(|surface| surface.min_size())
+        }
+
+        /// Returns the maximum size the window requests. `None` if it has no limit. A component of 0 means no
+        /// limit in the direction.
+        pub fn max_size(&self) -> Option<Size<i32, Logical>> {
+            if let Some(toplevel) = self.swindow.toplevel() {
+                let size = with_states(toplevel.wl_surface(), |states| {
+                    states.cached_state.current::<SurfaceCachedState>().max_size
+                });
+                return (size.w > 0 || size.h > 0).then_some(size);
+            }
+            self.swindow
+                .x11_surface()
+                .and_then(|surface| surface.max_size())
+        }
+
+        /// Returns true iff the window asked for attention by xdg-activation.
+        ///
+        /// `WM_HINTS` of X11 windows is not taken into account, as smithay doesn't expose it.
+        pub fn is_urgent(&self) -> bool {
+            self.inner.lock().unwrap().urgent
+        }
+
+        /// Marks the window urgent. It is cleared when the window is activated.
+        pub fn set_urgent(&self, urgent: bool) {
+            self.inner.lock().unwrap().urgent = urgent;
+        }
+
         pub fn on_commit(&self) {
             self.swindow.on_commit();
         }
 
+        /// Asks the client to close the window.
+        pub fn close(&self) {
+            use smithay::desktop::WindowSurface;
+
+            match self.swindow.underlying_surface() {
+                WindowSurface::Wayland(w) => w.send_close(),
+                WindowSurface::X11(w) => {
+                    let _ = w.close();
+                }
+            };
+        }
+
         pub fn surface_under<P: Into<Point<f64, Logical>>>(
             &self,
             point: P,
@@ -187,6 +304,9 @@ mod window {
         }
 
         fn set_activate(&self, activated: bool) {
+            if activated {
+                self.set_urgent(false);
+            }
             self.swindow.set_activate(activated);
         }
 
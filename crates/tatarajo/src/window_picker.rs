@@ -0,0 +1,147 @@
+use crate::action::{ActionFnI, ActionFocusWindow};
+use crate::input::capture::{KeyCapture, KeyCaptureResult};
+use crate::input::Key;
+use crate::util::Id;
+use crate::view::view::View;
+use crate::view::window::Window;
+use xkbcommon::xkb;
+
+/// Characters used for hints, in the order of ease to type.
+const HINT_CHARS: &str = "asdfghjklqwertyuiopzxcvbnm";
+const MAX_TITLE_LEN: usize = 60;
+
+struct Entry {
+    hint: String,
+    window_id: Id<Window>,
+    line: String,
+}
+
+/// Lists windows in all workspaces with hints. Typing a hint focuses the window.
+pub(crate) struct WindowPicker {
+    entries: Vec<Entry>,
+    typed: String,
+}
+
+impl WindowPicker {
+    /// Returns `None` if there is no window.
+    pub fn new(view: &View) -> Option<Self> {
+        let windows = view
+            .stackset()
+            .workspaces
+            .as_vec()
+            .iter()
+            .flat_map(|ws| ws.stack.as_vec().iter().map(move |&wid| (&ws.tag, wid)))
+            .filter_map(|(tag, wid)| Some((tag, view.window(wid)?)))
+            .collect::<Vec<_>>();
+        if windows.is_empty() {
+            return None;
+        }
+
+        let entries = hints(windows.len())
+            .into_iter()
+            .zip(windows)
+            .map(|(hint, (tag, window))| {
+                let title = window.title().unwrap_or_default();
+                let title = title.chars().take(MAX_TITLE_LEN).collect::<String>();
+                let line = format!(
+                    "{}  {:<8} {:<16} {}",
+                    hint,
+                    tag,
+                    window.app_id().unwrap_or_default(),
+                    title
+                );
+                Entry {
+                    hint,
+                    window_id: window.id(),
+                    line,
+                }
+            })
+            .collect();
+
+        Some(Self {
+            entries,
+            typed: String::new(),
+        })
+    }
+}
+
+impl KeyCapture for WindowPicker {
+    fn on_key(&mut self, key: &Key) -> KeyCaptureResult {
+        if key.keysym == xkb::Keysym::BackSpace {
+            self.typed.pop();
+            return KeyCaptureResult::Continue;
+        }
+        let Some(c) = char::from_u32(xkb::keysym_to_utf32(key.keysym)) else {
+            return KeyCaptureResult::Continue;
+        };
+        if !HINT_CHARS.contains(c) {
+            // E.g. modifier keys.
+            return KeyCaptureResult::Continue;
+        }
+
+        self.typed.push(c);
+        let mut candidates = self
+            .entries
+            .iter()
+            .filter(|entry| entry.hint.starts_with(&self.typed));
+        match candidates.next() {
+            None => KeyCaptureResult::Finish(None),
+            Some(entry) if entry.hint == self.typed => {
+                KeyCaptureResult::Finish(Some(ActionFocusWindow(entry.window_id).into_action()))
+            }
+            Some(_) => KeyCaptureResult::Continue,
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Select a window: {}", self.typed)];
+        lines.extend(
+            self.entries
+                .iter()
+                .filter(|entry| entry.hint.starts_with(&self.typed))
+                .map(|entry| entry.line.clone()),
+        );
+        lines
+    }
+}
+
+/// Returns `n` hints of the same length, so that no hint is a prefix of another.
+fn hints(n: usize) -> Vec<String> {
+    let chars = HINT_CHARS.chars().collect::<Vec<_>>();
+    let mut len = 1;
+    while chars.len().pow(len) < n {
+        len += 1;
+    }
+
+    (0..n)
+        .map(|mut i| {
+            let mut hint = vec![' '; len as usize];
+            for c in hint.iter_mut().rev() {
+                *c = chars[i % chars.len()];
+                i /= chars.len();
+            }
+            hint.into_iter().collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        n,
+        expected,
+        case(0, vec![]),
+        case(3, vec!["a", "s", "d"]),
+        case(26, HINT_CHARS.split("").filter(|s| !s.is_empty()).collect()),
+        case(28, vec!["aa", "as", "ad"])
+    )]
+    #[trace]
+    fn test_hints(n: usize, expected: Vec<&str>) {
+        let actual = hints(n);
+        assert_eq!(actual.len(), n);
+        assert_eq!(actual[..expected.len()], expected);
+    }
+}